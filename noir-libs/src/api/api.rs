@@ -1,4 +1,4 @@
-use crate::api::network::{download_package, get_latest_package_version, publish_package, yank_package};
+use crate::api::network::{download_package, get_advisories, get_latest_package_version, get_package_keys, package_version_exists, publish_package, yank_package};
 use crate::config::REGISTRY_URL;
 use std::path::Path;
 use crate::ops::package::package::PackagedTarball;
@@ -11,6 +11,18 @@ pub fn get_latest_package_version_api(package_name: &str) -> Result<String, Stri
     get_latest_package_version(get_latest_package_version_url(package_name))
 }
 
+pub fn package_version_exists_api(package_name: &str, version: &str) -> anyhow::Result<bool> {
+    package_version_exists(get_package_url(package_name, version, true).as_str())
+}
+
+pub fn get_package_keys_api(package_name: &str) -> anyhow::Result<Vec<String>> {
+    get_package_keys(get_package_keys_url(package_name).as_str())
+}
+
+pub fn get_advisories_api() -> anyhow::Result<String> {
+    get_advisories(get_advisories_url().as_str())
+}
+
 pub fn publish_package_api(tarball_path: &PackagedTarball, api_key: String) -> anyhow::Result<String> {
     publish_package(tarball_path, api_key, get_publish_package_url(tarball_path.name.as_str(), tarball_path.version.as_str()))
 }
@@ -36,6 +48,18 @@ fn get_latest_package_version_url(package_name: &str) -> String {
     format!("{}/packages/{}/versions/latest", REGISTRY_URL, package_name)
 }
 
+/// Retrieves the URL listing signing keys published by the package owners
+/// Example: http://localhost:3001/api/v1/packages/aztec/keys
+fn get_package_keys_url(package_name: &str) -> String {
+    format!("{}/packages/{}/keys", REGISTRY_URL, package_name)
+}
+
+/// Retrieves the URL of the advisory database
+/// Example: http://localhost:3001/api/v1/advisories
+fn get_advisories_url() -> String {
+    format!("{}/advisories", REGISTRY_URL)
+}
+
 /// Retrieves the URL for publishing a package to remote registry
 /// Example: http://localhost:3001/api/v1/packages/my_cool_package/0.1.0/publish
 fn get_publish_package_url(package_name: &str, version: &str) -> String {
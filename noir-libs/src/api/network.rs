@@ -39,6 +39,61 @@ pub fn download_package(output_path: &Path, url: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// Checks whether a package version is available in the remote registry.
+///
+/// # Arguments
+///
+/// * `url` - The url to download the package version from
+///
+/// # Returns
+///
+/// `true` if the package version exists, `false` if the registry responds with 404 Not Found.
+pub fn package_version_exists(url: &str) -> anyhow::Result<bool> {
+    let response = get(url)?;
+    if response.status().is_success() {
+        Ok(true)
+    } else if response.status() == reqwest::StatusCode::NOT_FOUND {
+        Ok(false)
+    } else {
+        bail!("Failed to check package version: Received status code {}", response.status())
+    }
+}
+
+/// Downloads the advisory database of the registry.
+///
+/// # Returns
+///
+/// The database as JSON.
+pub fn get_advisories(url: &str) -> anyhow::Result<String> {
+    let response = get(url)?;
+    if !response.status().is_success() {
+        bail!("Failed to download advisories: Received status code {}", response.status());
+    }
+    Ok(response.text()?)
+}
+
+#[derive(Debug, Deserialize)]
+struct PackageKeys {
+    keys: Vec<String>,
+}
+
+/// Retrieves hex encoded signing keys published by the owners of a package.
+///
+/// # Returns
+///
+/// The keys, empty if the package has no published keys (404 Not Found).
+pub fn get_package_keys(url: &str) -> anyhow::Result<Vec<String>> {
+    let response = get(url)?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(Vec::new());
+    }
+    if !response.status().is_success() {
+        bail!("Failed to get package keys: Received status code {}", response.status());
+    }
+    let keys: PackageKeys = serde_json::from_str(&response.text()?)?;
+    Ok(keys.keys)
+}
+
 #[derive(Debug, Deserialize)]
 struct Version {
     version: String,
@@ -204,6 +259,48 @@ mod tests {
         mock.assert();
     }
 
+    #[test]
+    fn test_package_version_exists() {
+        let mut server = mockito::Server::new();
+        let url = server.url();
+        let published = server.mock("GET", "/published").with_status(200).with_body("tarball").create();
+        let missing = server.mock("GET", "/missing").with_status(404).create();
+        let failing = server.mock("GET", "/failing").with_status(500).create();
+
+        assert!(package_version_exists(&format!("{}/published", url)).unwrap());
+        assert!(!package_version_exists(&format!("{}/missing", url)).unwrap());
+        assert!(package_version_exists(&format!("{}/failing", url)).is_err());
+        published.assert();
+        missing.assert();
+        failing.assert();
+    }
+
+    #[test]
+    fn test_get_advisories() {
+        let mut server = mockito::Server::new();
+        let url = server.url();
+        let mock = server.mock("GET", "/advisories").with_status(200).with_body(r#"{"advisories": []}"#).create();
+        let failing = server.mock("GET", "/failing").with_status(500).create();
+
+        assert_eq!(get_advisories(&format!("{}/advisories", url)).unwrap(), r#"{"advisories": []}"#);
+        assert!(get_advisories(&format!("{}/failing", url)).is_err());
+        mock.assert();
+        failing.assert();
+    }
+
+    #[test]
+    fn test_get_package_keys() {
+        let mut server = mockito::Server::new();
+        let url = server.url();
+        let published = server.mock("GET", "/keys").with_status(200).with_body(r#"{"keys": ["abcd"]}"#).create();
+        let missing = server.mock("GET", "/missing").with_status(404).create();
+
+        assert_eq!(get_package_keys(&format!("{}/keys", url)).unwrap(), vec!["abcd".to_string()]);
+        assert!(get_package_keys(&format!("{}/missing", url)).unwrap().is_empty());
+        published.assert();
+        missing.assert();
+    }
+
     #[test]
     fn test_get_latest_version() {
         let mut server = mockito::Server::new();
@@ -0,0 +1,555 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::{MANIFEST_FILE_NAME, VENDOR_CHECKSUM_FILE_NAME};
+use crate::metadata::read_package_dependencies;
+use crate::path::{get_cache_storage, get_package_dir};
+
+const ARCHIVE_EXTENSION: &str = "archive";
+
+/// A package version extracted into the dependencies folder
+/// Example: .noir-libs-deps/value_note/0.67.0
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CachedPackage {
+    pub name: String,
+    pub version: String,
+    pub dir: PathBuf,
+}
+
+/// Lists all package versions extracted into the dependencies folder.
+///
+/// # Arguments
+///
+/// * `cache_root` - The dependencies folder of a project.
+///
+/// # Returns
+///
+/// Cached packages sorted by name and version.
+pub fn list_cached_packages(cache_root: &Path) -> io::Result<Vec<CachedPackage>> {
+    let mut packages = Vec::new();
+    if !cache_root.is_dir() {
+        return Ok(packages);
+    }
+    for name_entry in fs::read_dir(cache_root)? {
+        let name_entry = name_entry?;
+        let name = name_entry.file_name().to_string_lossy().to_string();
+        // hidden folders (e.g. git checkouts) are not registry packages
+        if !name_entry.file_type()?.is_dir() || name.starts_with('.') {
+            continue;
+        }
+        for version_entry in fs::read_dir(name_entry.path())? {
+            let version_entry = version_entry?;
+            if !version_entry.file_type()?.is_dir() {
+                continue;
+            }
+            packages.push(CachedPackage {
+                name: name.clone(),
+                version: version_entry.file_name().to_string_lossy().to_string(),
+                dir: version_entry.path(),
+            });
+        }
+    }
+    packages.sort();
+    Ok(packages)
+}
+
+/// A package version of the resolved dependency graph of a project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedPackage {
+    pub name: String,
+    pub version: String,
+    /// Example: .noir-libs-deps/value_note/0.67.0, may not exist if the package is not fetched
+    pub dir: PathBuf,
+    /// Dependencies declared by the package as (package name, version) pairs
+    pub dependencies: Vec<(String, String)>,
+}
+
+/// The dependency graph of a project resolved from the manifests and the cached packages
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedGraph {
+    /// Dependencies declared by the project manifests as (package name, version) pairs
+    pub roots: BTreeSet<(String, String)>,
+    /// All reachable packages sorted by name and version
+    pub packages: Vec<ResolvedPackage>,
+    /// Dependencies which could not be resolved: path dependencies on missing folders and packages which are not fetched
+    pub unresolved: Vec<String>,
+    /// Git dependencies declared by the project manifests, they are not resolved
+    pub git_dependencies: Vec<GitDependency>,
+}
+
+/// A git dependency declared by a project manifest
+/// Example: aztec = { git = "https://github.com/AztecProtocol/aztec-packages", tag = "v0.67.0", directory = "noir-projects/aztec-nr/aztec" }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitDependency {
+    /// Name of the dependency in the `[dependencies]` table
+    pub name: String,
+    pub git: String,
+    pub tag: Option<String>,
+    pub directory: Option<String>,
+    /// The manifest declaring the dependency
+    pub manifest: PathBuf,
+}
+
+impl std::fmt::Display for GitDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} = {{ git = \"{}\" }} in {}", self.name, self.git, self.manifest.display())
+    }
+}
+
+/// Resolves every package version reachable from the dependencies declared in the given manifests,
+/// including sub-dependencies declared by the packages themselves. Followed are dependencies pointing
+/// into the dependencies folder or into a vendor folder. Other path dependencies (e.g. workspace members)
+/// are local packages, their manifests are resolved as additional roots.
+/// Sub-dependencies are resolved next to the package depending on them, in the same folder.
+///
+/// # Arguments
+///
+/// * `cache_root` - The dependencies folder of a project.
+/// * `manifests` - Paths to the project manifest files that are the roots of the dependency tree.
+pub fn resolve_graph(cache_root: &Path, manifests: &[PathBuf]) -> ResolvedGraph {
+    let mut roots = Vec::new();
+    let mut unresolved = Vec::new();
+    let mut git_dependencies = Vec::new();
+    let mut pending_manifests = manifests.to_vec();
+    let mut visited_manifests = BTreeSet::new();
+    while !pending_manifests.is_empty() {
+        let manifest = pending_manifests.remove(0);
+        if !visited_manifests.insert(normalize_path(&manifest)) {
+            continue;
+        }
+        let dependencies = match read_manifest_dependencies(&manifest) {
+            Ok(dependencies) => dependencies,
+            Err(e) => {
+                unresolved.push(e);
+                continue;
+            }
+        };
+        let manifest_dir = manifest.parent().unwrap_or(Path::new(""));
+        for (dependency_name, dependency) in dependencies.iter() {
+            if let Some(path) = dependency.get("path").and_then(|path| path.as_str()) {
+                let package_dir = normalize_path(&manifest_dir.join(path));
+                match get_stored_package(&package_dir, cache_root) {
+                    Some((name, version)) => roots.push((name, version, package_dir)),
+                    None if !package_dir.join(MANIFEST_FILE_NAME).is_file() => {
+                        unresolved.push(format!("{} = {{ path = \"{}\" }} in {}", dependency_name, path, manifest.display()))
+                    }
+                    // a local package (e.g. a sibling workspace member), its dependencies are part of the graph
+                    None => pending_manifests.push(package_dir.join(MANIFEST_FILE_NAME)),
+                }
+            } else if let Some(git) = dependency.get("git").and_then(|git| git.as_str()) {
+                let get_str = |key: &str| dependency.get(key).and_then(|value| value.as_str()).map(str::to_string);
+                git_dependencies.push(GitDependency {
+                    name: dependency_name.to_string(),
+                    git: git.to_string(),
+                    tag: get_str("tag"),
+                    directory: get_str("directory"),
+                    manifest: manifest.clone(),
+                });
+            }
+        }
+    }
+    let mut graph = resolve_packages(roots);
+    graph.unresolved.splice(0..0, unresolved);
+    graph.git_dependencies = git_dependencies;
+    graph
+}
+
+/// Resolves every package version reachable from the given (package name, version) pairs stored in the dependencies folder.
+pub fn resolve_graph_from_roots(cache_root: &Path, roots: BTreeSet<(String, String)>) -> ResolvedGraph {
+    resolve_packages(roots.into_iter()
+        .map(|(name, version)| {
+            let dir = get_package_dir(cache_root.to_path_buf(), &name, &version);
+            (name, version, dir)
+        })
+        .collect())
+}
+
+/// Resolves every package version reachable from the given (package name, version, folder) packages,
+/// sub-dependencies are resolved next to the package depending on them.
+pub fn resolve_stored_packages(roots: Vec<(String, String, PathBuf)>) -> ResolvedGraph {
+    resolve_packages(roots)
+}
+
+fn resolve_packages(roots: Vec<(String, String, PathBuf)>) -> ResolvedGraph {
+    let mut visited = BTreeSet::new();
+    let mut packages = Vec::new();
+    let mut unresolved = Vec::new();
+    let mut to_visit = roots.clone();
+    while let Some((name, version, dir)) = to_visit.pop() {
+        if !visited.insert((name.clone(), version.clone())) {
+            continue;
+        }
+        if !dir.join(MANIFEST_FILE_NAME).is_file() {
+            unresolved.push(format!("{}@{} is not available in {}", name, version, dir.display()));
+        }
+        // a package which cannot be read is reported, its sub-dependencies are unknown
+        let dependencies = match read_package_dependencies(&dir) {
+            Ok(dependencies) => dependencies,
+            Err(e) => {
+                unresolved.push(format!("{}@{} cannot be read: {:#}", name, version, e));
+                Vec::new()
+            }
+        };
+        // packages are stored with the <package>/<version> layout, sub-dependencies are stored next to them
+        let packages_root = dir.parent().and_then(Path::parent).unwrap_or(Path::new("")).to_path_buf();
+        to_visit.extend(dependencies.iter().map(|(name, version)| (name.clone(), version.clone(), get_package_dir(packages_root.clone(), name, version))));
+        packages.push(ResolvedPackage { name, version, dir, dependencies });
+    }
+    packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    unresolved.sort();
+    let roots = roots.into_iter().map(|(name, version, _)| (name, version)).collect();
+    ResolvedGraph { roots, packages, unresolved, git_dependencies: Vec::new() }
+}
+
+/// Recognizes a package folder with the `<package>/<version>` layout in the dependencies folder
+/// or in a vendor folder (a folder with the vendor checksum manifest).
+///
+/// # Returns
+///
+/// The (package name, version) of the package, `None` for other folders.
+pub fn get_stored_package(package_dir: &Path, cache_root: &Path) -> Option<(String, String)> {
+    let in_cache = package_dir.strip_prefix(normalize_path(cache_root)).is_ok_and(|path| path.components().count() == 2);
+    let packages_root = package_dir.parent()?.parent()?;
+    if !in_cache && !packages_root.join(VENDOR_CHECKSUM_FILE_NAME).is_file() {
+        return None;
+    }
+    let version = package_dir.file_name()?.to_string_lossy().to_string();
+    let name = package_dir.parent()?.file_name()?.to_string_lossy().to_string();
+    Some((name, version))
+}
+
+/// Finds path dependencies of a manifest pointing to packages stored in the dependencies folder or in a vendor folder.
+///
+/// # Returns
+///
+/// (package name, version) pairs, empty if the manifest cannot be read.
+pub fn get_stored_dependencies(manifest: &Path, cache_root: &Path) -> Vec<(String, String)> {
+    get_stored_dependency_dirs(manifest, cache_root).into_iter().map(|(name, version, _)| (name, version)).collect()
+}
+
+/// Like [`get_stored_dependencies`], with the folder each package is stored in.
+pub fn get_stored_dependency_dirs(manifest: &Path, cache_root: &Path) -> Vec<(String, String, PathBuf)> {
+    let manifest_dir = manifest.parent().unwrap_or(Path::new(""));
+    read_manifest_dependencies(manifest)
+        .map(|dependencies| dependencies.iter()
+            .filter_map(|(_, dependency)| dependency.get("path").and_then(|path| path.as_str()))
+            .filter_map(|path| {
+                let package_dir = normalize_path(&manifest_dir.join(path));
+                let (name, version) = get_stored_package(&package_dir, cache_root)?;
+                Some((name, version, package_dir))
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+// the [dependencies] table of a manifest, empty if the manifest has no dependencies
+fn read_manifest_dependencies(manifest: &Path) -> Result<toml_edit::Table, String> {
+    let content = fs::read_to_string(manifest).map_err(|e| format!("Cannot read {}: {}", manifest.display(), e))?;
+    let doc = content.parse::<toml_edit::DocumentMut>().map_err(|e| format!("{} is invalid TOML: {}", manifest.display(), e))?;
+    match doc.get("dependencies") {
+        Some(toml_edit::Item::Table(table)) => Ok(table.clone()),
+        Some(toml_edit::Item::Value(toml_edit::Value::InlineTable(table))) => Ok(table.clone().into_table()),
+        _ => Ok(toml_edit::Table::new()),
+    }
+}
+
+/// Removes `.` and `..` components without accessing the filesystem
+/// Example: /project/crates/a/../../.noir-libs-deps -> /project/.noir-libs-deps
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Collects every package version reachable from the dependencies declared in the given manifests.
+///
+/// # Returns
+///
+/// A set of (package name, version) pairs.
+pub fn reachable_packages(cache_root: &Path, manifests: &[PathBuf]) -> BTreeSet<(String, String)> {
+    resolve_graph(cache_root, manifests).packages.into_iter()
+        .map(|package| (package.name, package.version))
+        .collect()
+}
+
+/// Removes cached package versions (and their downloaded archives) that are no longer
+/// reachable from the dependencies declared in the given manifests.
+///
+/// # Arguments
+///
+/// * `cache_root` - The dependencies folder of a project.
+/// * `manifests` - Paths to the project manifest files that are the roots of the dependency tree.
+///
+/// # Returns
+///
+/// The removed packages.
+pub fn remove_unreachable_packages(cache_root: &Path, manifests: &[PathBuf]) -> io::Result<Vec<CachedPackage>> {
+    let reachable = reachable_packages(cache_root, manifests);
+    let mut removed = Vec::new();
+    for package in list_cached_packages(cache_root)? {
+        if reachable.contains(&(package.name.clone(), package.version.clone())) {
+            continue;
+        }
+        fs::remove_dir_all(&package.dir)?;
+        let archive = get_cache_storage(cache_root.to_path_buf(), &package.name, &package.version);
+        if archive.is_file() {
+            fs::remove_file(archive)?;
+        }
+        remove_dir_if_empty(&cache_root.join(&package.name))?;
+        removed.push(package);
+    }
+    Ok(removed)
+}
+
+/// Lists downloaded package archives left in the dependencies folder.
+/// Example: .noir-libs-deps/value_note_0.67.0.archive
+///
+/// # Returns
+///
+/// Tuples of (package name, version, archive path) sorted by archive path.
+pub fn list_archives(cache_root: &Path) -> io::Result<Vec<(String, String, PathBuf)>> {
+    let mut archives = Vec::new();
+    if !cache_root.is_dir() {
+        return Ok(archives);
+    }
+    for entry in fs::read_dir(cache_root)? {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some(ARCHIVE_EXTENSION) {
+            continue;
+        }
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        // package names may contain "_" but versions do not, so split on the last one
+        if let Some((name, version)) = stem.rsplit_once('_') {
+            archives.push((name.to_string(), version.to_string(), path.clone()));
+        }
+    }
+    archives.sort_by(|a, b| a.2.cmp(&b.2));
+    Ok(archives)
+}
+
+/// Removes package archives which are not needed anymore: archives of packages that are
+/// already extracted next to them and archives of packages not reachable from the given manifests.
+///
+/// # Returns
+///
+/// Paths of the removed archives.
+pub fn remove_stale_archives(cache_root: &Path, manifests: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let reachable = reachable_packages(cache_root, manifests);
+    let mut removed = Vec::new();
+    for (name, version, archive) in list_archives(cache_root)? {
+        let extracted = get_package_dir(cache_root.to_path_buf(), &name, &version).is_dir();
+        if extracted || !reachable.contains(&(name, version)) {
+            fs::remove_file(&archive)?;
+            removed.push(archive);
+        }
+    }
+    Ok(removed)
+}
+
+/// Calculates the total size in bytes of all files in the given file or directory.
+pub fn disk_usage(path: &Path) -> io::Result<u64> {
+    if !path.exists() {
+        return Ok(0);
+    }
+    let mut size = 0;
+    for entry in walkdir::WalkDir::new(path) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+/// Formats a size in bytes to a human readable form
+/// Example: 1536 -> 1.5 KiB
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn remove_dir_if_empty(dir: &Path) -> io::Result<()> {
+    if dir.is_dir() && fs::read_dir(dir)?.next().is_none() {
+        fs::remove_dir(dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MANIFEST_FILE_NAME;
+    use tempfile::tempdir;
+
+    fn write_package(cache_root: &Path, name: &str, version: &str, dependencies: &str) {
+        let dir = get_package_dir(cache_root.to_path_buf(), name, version);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(MANIFEST_FILE_NAME), format!("[dependencies]\n{}", dependencies)).unwrap();
+        fs::write(get_cache_storage(cache_root.to_path_buf(), name, version), "archive").unwrap();
+    }
+
+    #[test]
+    fn test_reachable_packages_include_sub_dependencies() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().join("deps");
+        write_package(&cache_root, "a", "1.0.0", "b = { path = \"../../b/2.0.0\" }\n");
+        write_package(&cache_root, "b", "2.0.0", "");
+        write_package(&cache_root, "c", "3.0.0", "");
+        let manifest = temp_dir.path().join(MANIFEST_FILE_NAME);
+        fs::write(&manifest, "[dependencies]\na = { path = \"deps/a/1.0.0\" }\n").unwrap();
+
+        let reachable = reachable_packages(&cache_root, &[manifest]);
+
+        assert_eq!(reachable.len(), 2);
+        assert!(reachable.contains(&("a".to_string(), "1.0.0".to_string())));
+        assert!(reachable.contains(&("b".to_string(), "2.0.0".to_string())));
+    }
+
+    #[test]
+    fn test_resolve_graph() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().join("deps");
+        write_package(&cache_root, "a", "1.0.0", "b = { path = \"../../b/2.0.0\" }\n");
+        write_package(&cache_root, "b", "2.0.0", "");
+        let manifest = temp_dir.path().join(MANIFEST_FILE_NAME);
+        fs::write(&manifest, "[dependencies]\na = { path = \"deps/a/1.0.0\" }\nmissing = { path = \"./deps/missing/1.0.0\" }\nmember = { path = \"../member\" }\n").unwrap();
+
+        let graph = resolve_graph(&cache_root, &[manifest]);
+
+        assert_eq!(graph.roots.len(), 2);
+        let names: Vec<&str> = graph.packages.iter().map(|package| package.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "missing"]);
+        assert_eq!(graph.packages[0].dependencies, vec![("b".to_string(), "2.0.0".to_string())]);
+        assert!(graph.packages[2].dependencies.is_empty());
+        assert_eq!(graph.unresolved.len(), 2);
+        assert!(graph.unresolved[0].starts_with("member = { path = \"../member\" }"));
+        assert!(graph.unresolved[1].starts_with("missing@1.0.0 is not available"));
+    }
+
+    #[test]
+    fn test_resolve_graph_in_vendor_folder() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().join("deps");
+        let vendor_dir = temp_dir.path().join("vendor");
+        write_package(&vendor_dir, "a", "1.0.0", "b = { path = \"../../b/2.0.0\" }\n");
+        write_package(&vendor_dir, "b", "2.0.0", "");
+        let manifest = temp_dir.path().join(MANIFEST_FILE_NAME);
+        fs::write(&manifest, "[dependencies]\nalias = { path = \"vendor/a/1.0.0\" }\naztec = { git = \"https://github.com/AztecProtocol/aztec-packages\" }\n").unwrap();
+
+        // not a vendor folder without the checksum manifest
+        assert!(resolve_graph(&cache_root, &[manifest.clone()]).packages.is_empty());
+
+        fs::write(vendor_dir.join(VENDOR_CHECKSUM_FILE_NAME), "{}").unwrap();
+        let graph = resolve_graph(&cache_root, &[manifest.clone()]);
+
+        let packages: Vec<(&str, &Path)> = graph.packages.iter().map(|package| (package.name.as_str(), package.dir.as_path())).collect();
+        assert_eq!(packages, vec![("a", vendor_dir.join("a/1.0.0").as_path()), ("b", vendor_dir.join("b/2.0.0").as_path())]);
+        assert!(graph.unresolved.is_empty());
+        assert_eq!(graph.git_dependencies.len(), 1);
+        assert_eq!(get_stored_dependencies(&manifest, &cache_root), vec![("a".to_string(), "1.0.0".to_string())]);
+    }
+
+    #[test]
+    fn test_resolve_graph_follows_local_packages() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().join("deps");
+        write_package(&cache_root, "aztec", "0.67.0", "");
+        for (member, dependencies) in [("a", "b = { path = \"../b\" }\n"), ("b", "aztec = { path = \"../deps/aztec/0.67.0\" }\na = { path = \"../a\" }\n")] {
+            fs::create_dir_all(temp_dir.path().join(member)).unwrap();
+            fs::write(temp_dir.path().join(member).join(MANIFEST_FILE_NAME), format!("[package]\nname = \"{}\"\n\n[dependencies]\n{}", member, dependencies)).unwrap();
+        }
+
+        let graph = resolve_graph(&cache_root, &[temp_dir.path().join("a").join(MANIFEST_FILE_NAME)]);
+
+        let names: Vec<&str> = graph.packages.iter().map(|package| package.name.as_str()).collect();
+        assert_eq!(names, vec!["aztec"]);
+        assert!(graph.unresolved.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_graph_reports_unreadable_packages() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().join("deps");
+        write_package(&cache_root, "a", "1.0.0", "");
+        fs::write(cache_root.join("a/1.0.0").join(crate::config::METADATA_FILE_NAME), "not json").unwrap();
+        let manifest = temp_dir.path().join(MANIFEST_FILE_NAME);
+        fs::write(&manifest, "[dependencies]\na = { path = \"deps/a/1.0.0\" }\n").unwrap();
+
+        let graph = resolve_graph(&cache_root, &[manifest]);
+
+        assert_eq!(graph.unresolved.len(), 1);
+        assert!(graph.unresolved[0].starts_with("a@1.0.0 cannot be read"), "{}", graph.unresolved[0]);
+    }
+
+    #[test]
+    fn test_remove_unreachable_packages() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().join("deps");
+        write_package(&cache_root, "a", "1.0.0", "b = { path = \"../../b/2.0.0\" }\n");
+        write_package(&cache_root, "b", "2.0.0", "");
+        write_package(&cache_root, "b", "1.0.0", "");
+        write_package(&cache_root, "c", "3.0.0", "");
+        let manifest = temp_dir.path().join(MANIFEST_FILE_NAME);
+        fs::write(&manifest, "[dependencies]\na = { path = \"deps/a/1.0.0\" }\n").unwrap();
+
+        let removed = remove_unreachable_packages(&cache_root, &[manifest]).unwrap();
+
+        assert_eq!(removed.len(), 2);
+        assert!(cache_root.join("a/1.0.0").is_dir());
+        assert!(cache_root.join("b/2.0.0").is_dir());
+        assert!(!cache_root.join("b/1.0.0").exists());
+        assert!(!cache_root.join("b_1.0.0.archive").exists());
+        assert!(!cache_root.join("c").exists());
+        assert!(!cache_root.join("c_3.0.0.archive").exists());
+    }
+
+    #[test]
+    fn test_remove_stale_archives() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().join("deps");
+        write_package(&cache_root, "value_note", "1.0.0", "");
+        fs::write(get_cache_storage(cache_root.clone(), "not_extracted", "1.0.0"), "archive").unwrap();
+        fs::write(get_cache_storage(cache_root.clone(), "unused", "1.0.0"), "archive").unwrap();
+        let manifest = temp_dir.path().join(MANIFEST_FILE_NAME);
+        fs::write(
+            &manifest,
+            "[dependencies]\nvalue_note = { path = \"deps/value_note/1.0.0\" }\nnot_extracted = { path = \"deps/not_extracted/1.0.0\" }\n",
+        ).unwrap();
+
+        let removed = remove_stale_archives(&cache_root, &[manifest]).unwrap();
+
+        assert_eq!(removed.len(), 2);
+        assert!(!cache_root.join("value_note_1.0.0.archive").exists());
+        assert!(!cache_root.join("unused_1.0.0.archive").exists());
+        assert!(cache_root.join("not_extracted_1.0.0.archive").exists());
+        assert!(cache_root.join("value_note/1.0.0").is_dir());
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
@@ -1,4 +1,6 @@
 pub const DEPENDENCIES_FOLDER_NAME: &str = ".noir-libs-deps";
+// Git dependencies are checked out in this folder inside the dependencies folder e.g. .noir-libs-deps/.git-checkouts
+pub const GIT_CHECKOUTS_FOLDER_NAME: &str = ".git-checkouts";
 pub const MANIFEST_FILE_NAME: &str = "Nargo.toml";
 pub const REGISTRY_URL: &str = "https://api.noir-libs.org/api/v1";
 // pub const REGISTRY_URL: &str = "http://localhost:3001/api/v1";
@@ -7,4 +9,22 @@ pub const REGISTRY_HOME_URL: &str = "https://noir-libs.org";
 // Packaged projects (packages) are generated in this folder e.g. target/package/my_cool_package_0.1.1
 pub const PACKAGING_OUTPUT_FOLDER_PATH: &str = "target/package";
 // The name of ENV variable that contains the API key - required for package publishing
-pub const API_KEY_ENV_VAR_NAME: &str = "NOIR_LIBS_API_KEY";
\ No newline at end of file
+pub const API_KEY_ENV_VAR_NAME: &str = "NOIR_LIBS_API_KEY";
+// Metadata file written into packaged tarballs, consumers resolve sub-dependencies from it
+pub const METADATA_FILE_NAME: &str = "noir-libs.json";
+// Gitignore-style file listing files which are not packaged
+pub const PACKAGE_IGNORE_FILE_NAME: &str = ".noir-libs-ignore";
+// Signature of the package contents written into signed tarballs
+pub const SIGNATURE_FILE_NAME: &str = "noir-libs.sig";
+// Project file with trusted package signing keys and the policy for unsigned packages
+pub const TRUST_FILE_NAME: &str = "noir-libs-trust.toml";
+// Signing keys of package owners are cached in this folder inside the dependencies folder e.g. .noir-libs-deps/.keys
+pub const KEYS_FOLDER_NAME: &str = ".keys";
+// The name of ENV variable with the path to the signing key used by --sign
+pub const SIGNING_KEY_ENV_VAR_NAME: &str = "NOIR_LIBS_SIGNING_KEY";
+// Project file with audit, license and dependency policies
+pub const POLICY_FILE_NAME: &str = "noir-libs-policy.toml";
+// Advisory database downloaded from the registry is cached in this file inside the dependencies folder
+pub const ADVISORY_DB_FILE_NAME: &str = ".advisory-db.json";
+// Checksums of vendored files written into the vendor folder, used to detect drift
+pub const VENDOR_CHECKSUM_FILE_NAME: &str = "noir-libs-vendor.json";
@@ -4,8 +4,10 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use ignore::WalkBuilder;
-use crate::config::DEPENDENCIES_FOLDER_NAME;
+use ignore::gitignore::GitignoreBuilder;
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::{Match, WalkBuilder};
+use crate::config::{DEPENDENCIES_FOLDER_NAME, PACKAGE_IGNORE_FILE_NAME};
 
 pub fn prepare_cache_dir(manifest_dir: &PathBuf) -> PathBuf {
     let cache_dir = manifest_dir.join(&DEPENDENCIES_FOLDER_NAME);
@@ -36,18 +38,68 @@ pub fn new_dir_replace_if_exists(path: &Path) -> std::io::Result<()> {
     Ok(())
 }
 
+/// Copies files from `src` to `dest` respecting `.gitignore` and `.noir-libs-ignore` files.
+///
+/// # Arguments
+///
+/// * `ignore_folders` - Names of folders which are never copied.
+/// * `ignore_files` - Names of files which are never copied.
+/// * `include` - Gitignore-style globs relative to `src`, only matching files are copied if not empty.
+/// * `exclude` - Gitignore-style globs relative to `src` of files which are not copied.
 pub fn copy_all(
     src: &Path,
     dest: &Path,
     ignore_folders: &[&str],
     ignore_files: &[&str],
+    include: &[String],
+    exclude: &[String],
 ) -> io::Result<()> {
-    let dest_clone = dest.to_path_buf().clone();
+    for relative_path in walk_files(src, Some(dest), ignore_folders, ignore_files, include, exclude)? {
+        let dest_path = dest.join(&relative_path);
+        // folders are created for copied files only, so folders without included files are skipped
+        if let Some(parent) = dest_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(src.join(&relative_path), &dest_path)?;
+    }
+    Ok(())
+}
+
+/// Lists the files `copy_all` would copy from `src`, without copying them.
+///
+/// # Returns
+///
+/// Paths relative to `src` in the walk order (sorted by name within each folder).
+pub fn list_files(
+    src: &Path,
+    ignore_folders: &[&str],
+    ignore_files: &[&str],
+    include: &[String],
+    exclude: &[String],
+) -> io::Result<Vec<PathBuf>> {
+    walk_files(src, None, ignore_folders, ignore_files, include, exclude)
+}
+
+fn walk_files(
+    src: &Path,
+    dest: Option<&Path>,
+    ignore_folders: &[&str],
+    ignore_files: &[&str],
+    include: &[String],
+    exclude: &[String],
+) -> io::Result<Vec<PathBuf>> {
+    let overrides = build_overrides(src, include, exclude)?;
+
+    let dest_clone = dest.map(Path::to_path_buf);
     let ignore_files_clone: Vec<String> = ignore_files.iter().map(|s| s.to_string()).collect();
     let ignore_folders_clone: Vec<String> = ignore_folders.iter().map(|s| s.to_string()).collect();
+    let mut files = Vec::new();
     for entry in WalkBuilder::new(src)
         // copy hidden files (starting with .)
         .hidden(false)
+        .add_custom_ignore_filename(PACKAGE_IGNORE_FILE_NAME)
+        .overrides(overrides)
+        .sort_by_file_name(|a, b| a.cmp(b))
         .filter_entry(move |e| {
             let path = e.path();
 
@@ -57,7 +109,7 @@ pub fn copy_all(
             }
 
             // ignore dest folder
-            if path.starts_with(&dest_clone) {
+            if dest_clone.as_ref().is_some_and(|dest| path.starts_with(dest)) {
                 return false;
             }
 
@@ -86,21 +138,98 @@ pub fn copy_all(
             }
         };
 
-        println !("Processing file: {:?}", entry.path());
-
         let path = entry.path();
+        if path.is_file() {
+            files.push(path.strip_prefix(src).unwrap_or(path).to_path_buf());
+        }
+    }
+    Ok(files)
+}
 
-        let relative_path = path.strip_prefix(src).unwrap_or(path);
-        let dest_path = dest.join(relative_path);
+/// Checks whether `copy_all` would copy a file, also for files which do not exist (e.g. deleted files).
+///
+/// Applied are the same rules: ignored folder and file names, `include`/`exclude` globs and the
+/// `.gitignore` (inside a git repository) and `.noir-libs-ignore` files of the file's folders.
+///
+/// # Arguments
+///
+/// * `relative_path` - Path of the file relative to `src`.
+pub fn is_file_included(
+    src: &Path,
+    relative_path: &Path,
+    ignore_folders: &[&str],
+    ignore_files: &[&str],
+    include: &[String],
+    exclude: &[String],
+) -> io::Result<bool> {
+    let path = src.join(relative_path);
+    if ignore_files.iter().any(|ignored| path.ends_with(ignored)) {
+        return Ok(false);
+    }
+    let is_ignored_folder = |name: &std::ffi::OsStr| ignore_folders.iter().any(|ignored| name == *ignored);
+    if relative_path.components().any(|component| is_ignored_folder(component.as_os_str())) {
+        return Ok(false);
+    }
+    let overrides = build_overrides(src, include, exclude)?;
+    let git_root = src.ancestors().find(|dir| dir.join(".git").exists());
+    let top_folder = git_root.unwrap_or(src);
+    // like the walker, a folder which is skipped skips all files in it
+    let mut levels: Vec<&Path> = path.ancestors().take_while(|level| *level != src && level.starts_with(src)).collect();
+    levels.reverse();
+    for level in levels {
+        let is_dir = level != path;
+        // include and exclude globs take precedence over ignore files
+        match overrides.matched(level, is_dir) {
+            Match::Whitelist(_) => continue,
+            Match::Ignore(_) => return Ok(false),
+            Match::None => {}
+        }
+        if is_ignored_by_files(level, is_dir, top_folder, git_root.is_some())? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
 
-        if path.is_dir() {
-            fs::create_dir_all(&dest_path)?;
-        } else if path.is_file() {
-            fs::copy(&path, &dest_path)?;
+// rules of deeper folders and of .noir-libs-ignore files win
+fn is_ignored_by_files(path: &Path, is_dir: bool, top_folder: &Path, in_git_repository: bool) -> io::Result<bool> {
+    let mut folders: Vec<&Path> = path.parent().map_or(Vec::new(), |parent| parent.ancestors().take_while(|dir| dir.starts_with(top_folder)).collect());
+    folders.reverse();
+    let mut ignored = false;
+    for folder in folders {
+        for ignore_file_name in [".gitignore", PACKAGE_IGNORE_FILE_NAME] {
+            let ignore_file = folder.join(ignore_file_name);
+            if !ignore_file.is_file() || (ignore_file_name == ".gitignore" && !in_git_repository) {
+                continue;
+            }
+            let mut builder = GitignoreBuilder::new(folder);
+            if let Some(e) = builder.add(&ignore_file) {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+            }
+            let gitignore = builder.build().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            match gitignore.matched(path, is_dir) {
+                Match::Ignore(_) => ignored = true,
+                Match::Whitelist(_) => ignored = false,
+                Match::None => {}
+            }
         }
     }
+    Ok(ignored)
+}
+
+fn build_overrides(src: &Path, include: &[String], exclude: &[String]) -> io::Result<Override> {
+    let mut overrides = OverrideBuilder::new(src);
+    for glob in include {
+        overrides.add(glob).map_err(|e| invalid_glob(glob, e))?;
+    }
+    for glob in exclude {
+        overrides.add(&format!("!{}", glob)).map_err(|e| invalid_glob(glob, e))?;
+    }
+    overrides.build().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
 
-    Ok(())
+fn invalid_glob(glob: &str, error: ignore::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid packaging glob \"{}\": {}", glob, error))
 }
 
 #[cfg(test)]
@@ -134,6 +263,77 @@ mod tests {
         }
     }
 
+    fn write_files(root: &Path, files: &[&str]) {
+        for file in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, file).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_copy_all_include_exclude() {
+        let temp_dir = tempdir().unwrap();
+        let src = temp_dir.path().join("src_package");
+        let dest = temp_dir.path().join("dest");
+        write_files(&src, &["Nargo.toml", "src/lib.nr", "src/test/fixtures.nr", "benches/proof.bin", "README.md"]);
+
+        copy_all(&src, &dest, &[], &[], &["/Nargo.toml".to_string(), "src/**".to_string()], &["src/test/**".to_string()]).unwrap();
+
+        assert!(dest.join("Nargo.toml").is_file());
+        assert!(dest.join("src/lib.nr").is_file());
+        assert!(!dest.join("src/test").exists());
+        assert!(!dest.join("benches").exists());
+        assert!(!dest.join("README.md").exists());
+    }
+
+    #[test]
+    fn test_copy_all_ignore_file() {
+        let temp_dir = tempdir().unwrap();
+        let src = temp_dir.path().join("src_package");
+        let dest = temp_dir.path().join("dest");
+        write_files(&src, &["Nargo.toml", "src/lib.nr", "tests/fixture.json", "proofs/a.proof"]);
+        fs::write(src.join(PACKAGE_IGNORE_FILE_NAME), "tests/\n*.proof\n").unwrap();
+
+        copy_all(&src, &dest, &[], &[], &[], &[]).unwrap();
+
+        assert!(dest.join("Nargo.toml").is_file());
+        assert!(dest.join("src/lib.nr").is_file());
+        assert!(!dest.join("tests").exists());
+        assert!(!dest.join("proofs").exists());
+    }
+
+    #[test]
+    fn test_is_file_included_matches_copy_all() {
+        let temp_dir = tempdir().unwrap();
+        let src = temp_dir.path().join("src_package");
+        let dest = temp_dir.path().join("dest");
+        let files = ["Nargo.toml", "src/lib.nr", "src/test/fixtures.nr", "tests/fixture.json", "proofs/a.proof", "target/out.json", "README.md"];
+        write_files(&src, &files);
+        fs::write(src.join(PACKAGE_IGNORE_FILE_NAME), "tests/\n*.proof\n").unwrap();
+        let include = ["/Nargo.toml".to_string(), "src/**".to_string(), "tests/**".to_string(), "proofs/**".to_string(), "target/**".to_string()];
+        let exclude = ["src/test/**".to_string()];
+
+        copy_all(&src, &dest, &["target"], &[], &include, &exclude).unwrap();
+
+        for file in files {
+            let included = is_file_included(&src, Path::new(file), &["target"], &[], &include, &exclude).unwrap();
+            assert_eq!(included, dest.join(file).is_file(), "{}", file);
+        }
+        // files which do not exist are matched too
+        assert!(is_file_included(&src, Path::new("src/deleted.nr"), &[], &[], &[], &[]).unwrap());
+        assert!(!is_file_included(&src, Path::new("tests/deleted.json"), &[], &[], &[], &[]).unwrap());
+    }
+
+    #[test]
+    fn test_copy_all_invalid_glob() {
+        let temp_dir = tempdir().unwrap();
+
+        let result = copy_all(temp_dir.path(), &temp_dir.path().join("dest"), &[], &[], &["src/[".to_string()], &[]);
+
+        assert!(result.unwrap_err().to_string().contains("src/["));
+    }
+
     #[test]
     fn test_ensure_dir_already_exists() {
         let temp_dir = tempdir().unwrap();
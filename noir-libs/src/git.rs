@@ -0,0 +1,324 @@
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::MANIFEST_FILE_NAME;
+use crate::path::get_git_checkout_dir;
+
+/// Locates the system `git` executable.
+pub fn find_git() -> Result<PathBuf> {
+    which::which("git").context("Unable to find git executable. Please install git and make sure it is available in PATH.")
+}
+
+/// Clones a git dependency into the cache or updates an existing checkout to the requested tag.
+///
+/// # Arguments
+///
+/// * `cache_root` - The root directory of the cache.
+/// * `url` - The git repository URL (or a local path).
+/// * `tag` - The tag (or branch) to check out. The default branch is used if not provided.
+/// * `directory` - The package subdirectory inside the repository.
+/// * `offline` - Use only an existing checkout, never contact the remote.
+///
+/// # Returns
+///
+/// Returns the path to the package inside the checkout.
+pub fn checkout_git_dependency(
+    cache_root: &Path,
+    url: &str,
+    tag: Option<&str>,
+    directory: Option<&str>,
+    offline: bool,
+) -> Result<PathBuf> {
+    if let Some(tag) = tag {
+        if tag.is_empty() || tag.starts_with('-') || tag.contains('\\') || tag.contains("..") {
+            bail!("Invalid tag \"{}\" of git repository {}. Tags must not be empty, start with \"-\" or contain \"\\\" or \"..\"", tag, url);
+        }
+    }
+    if let Some(directory) = directory {
+        if !Path::new(directory).components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir)) {
+            bail!("Invalid directory \"{}\" of git repository {}. The directory must be a relative path inside the repository", directory, url);
+        }
+    }
+    let checkout_dir = get_git_checkout_dir(cache_root.to_path_buf(), url, tag);
+    let reference = tag.unwrap_or("HEAD");
+
+    if checkout_dir.join(".git").is_dir() {
+        if !offline {
+            run_git(Some(&checkout_dir), &["fetch", "--depth", "1", "origin", reference])?;
+            run_git(Some(&checkout_dir), &["checkout", "--force", "FETCH_HEAD"])?;
+        }
+    } else if offline {
+        bail!("Git repository {} ({}) is not available in the cache and cannot be cloned in offline mode", url, reference);
+    } else {
+        if checkout_dir.exists() {
+            std::fs::remove_dir_all(&checkout_dir)?;
+        }
+        std::fs::create_dir_all(&checkout_dir)?;
+        let checkout_dir_str = checkout_dir.to_str().context("Checkout path is not valid UTF-8")?;
+        let mut args = vec!["clone", "--depth", "1"];
+        if let Some(tag) = tag {
+            args.extend(["--branch", tag]);
+        }
+        args.extend(["--", url, checkout_dir_str]);
+        if let Err(e) = run_git(None, &args) {
+            std::fs::remove_dir_all(&checkout_dir).ok();
+            return Err(e);
+        }
+    }
+
+    let package_dir = match directory {
+        Some(directory) => checkout_dir.join(directory),
+        None => checkout_dir,
+    };
+    if !package_dir.join(MANIFEST_FILE_NAME).is_file() {
+        bail!("Git repository {} ({}) does not contain {} in directory \"{}\"", url, reference, &MANIFEST_FILE_NAME, directory.unwrap_or(""));
+    }
+    Ok(package_dir)
+}
+
+/// Git repository state of a package directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepositoryStatus {
+    /// The checked out commit SHA
+    pub commit: String,
+    /// Path of the package directory relative to the repository root, empty for the root
+    pub path_in_repository: String,
+    /// Paths relative to the package directory with uncommitted changes (including untracked files)
+    pub changed_files: Vec<String>,
+    /// Paths relative to the package directory of deleted tracked files
+    pub deleted_files: Vec<String>,
+}
+
+/// Reads the git status of a package directory.
+///
+/// # Returns
+///
+/// `None` if the directory is not inside a git repository.
+pub fn repository_status(package_dir: &Path) -> Result<Option<RepositoryStatus>> {
+    if !package_dir.ancestors().any(|dir| dir.join(".git").exists()) {
+        return Ok(None);
+    }
+    let path_in_repository = run_git(Some(package_dir), &["rev-parse", "--show-prefix"])?.trim().trim_end_matches('/').to_string();
+    let commit = run_git(Some(package_dir), &["rev-parse", "HEAD"])
+        .context("Unable to read the current commit. Commit the package first or pass --allow-dirty.")?
+        .trim()
+        .to_string();
+
+    let status = run_git(Some(package_dir), &["status", "--porcelain=v1", "-z", "--untracked-files=all", "--", "."])?;
+    let mut changed_files = Vec::new();
+    let mut deleted_files = Vec::new();
+    // paths are relative to the repository root
+    let relative_to_package = |path: &str| match path_in_repository.as_str() {
+        "" => path.to_string(),
+        prefix => path.strip_prefix(prefix).map(|path| path.trim_start_matches('/')).unwrap_or(path).to_string(),
+    };
+    // entries have the "XY PATH" format, renames and copies are followed by an entry with the original path
+    let mut entries = status.split('\0');
+    while let Some(entry) = entries.next() {
+        if entry.is_empty() {
+            continue;
+        }
+        let (Some(code), Some(path)) = (entry.get(..2), entry.get(3..)) else {
+            bail!("Unexpected git status entry \"{}\"", entry);
+        };
+        if code.contains(['R', 'C']) {
+            let original_path = entries.next().context("Git status entry of a rename or copy has no original path")?;
+            if code.contains('R') {
+                deleted_files.push(relative_to_package(original_path));
+            }
+        }
+        if code.contains('D') {
+            deleted_files.push(relative_to_package(path));
+        } else {
+            changed_files.push(relative_to_package(path));
+        }
+    }
+    Ok(Some(RepositoryStatus { commit, path_in_repository, changed_files, deleted_files }))
+}
+
+fn run_git(current_dir: Option<&Path>, args: &[&str]) -> Result<String> {
+    let mut command = Command::new(find_git()?);
+    command.args(args);
+    if let Some(dir) = current_dir {
+        command.current_dir(dir);
+    }
+    let output = command.output().context("Failed to run git")?;
+    if !output.status.success() {
+        bail!("git {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com", "-c", "init.defaultBranch=main"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn commit_package(work_dir: &Path, version: &str, tag: &str) {
+        let package_dir = work_dir.join("packages/my_lib");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(package_dir.join(MANIFEST_FILE_NAME), format!("[package]\nname = \"my_lib\"\nversion = \"{}\"\n", version)).unwrap();
+        git(work_dir, &["add", "-A"]);
+        git(work_dir, &["commit", "-m", version]);
+        git(work_dir, &["tag", tag]);
+        git(work_dir, &["push", "origin", "HEAD", "--tags"]);
+    }
+
+    // creates a local bare repository with a package in "packages/my_lib"
+    fn setup_bare_repository(root: &Path) -> (String, PathBuf) {
+        let bare = root.join("remote.git");
+        let work = root.join("work");
+        fs::create_dir_all(&bare).unwrap();
+        fs::create_dir_all(&work).unwrap();
+        git(&bare, &["init", "--bare"]);
+        git(&work, &["init"]);
+        git(&work, &["remote", "add", "origin", bare.to_str().unwrap()]);
+        commit_package(&work, "0.1.0", "v0.1.0");
+        (bare.to_str().unwrap().to_string(), work)
+    }
+
+    #[test]
+    fn test_checkout_git_dependency_tag() {
+        let temp_dir = tempdir().unwrap();
+        let (url, work) = setup_bare_repository(temp_dir.path());
+        commit_package(&work, "0.2.0", "v0.2.0");
+        let cache_root = temp_dir.path().join("deps");
+
+        let package_dir = checkout_git_dependency(&cache_root, &url, Some("v0.1.0"), Some("packages/my_lib"), false).unwrap();
+
+        assert_eq!(package_dir, get_git_checkout_dir(cache_root.clone(), &url, Some("v0.1.0")).join("packages/my_lib"));
+        let manifest = fs::read_to_string(package_dir.join(MANIFEST_FILE_NAME)).unwrap();
+        assert!(manifest.contains("0.1.0"));
+
+        // an existing checkout is reused in offline mode
+        let package_dir = checkout_git_dependency(&cache_root, &url, Some("v0.1.0"), Some("packages/my_lib"), true).unwrap();
+        assert!(package_dir.join(MANIFEST_FILE_NAME).is_file());
+    }
+
+    #[test]
+    fn test_checkout_git_dependency_updates_existing_checkout() {
+        let temp_dir = tempdir().unwrap();
+        let (url, work) = setup_bare_repository(temp_dir.path());
+        let cache_root = temp_dir.path().join("deps");
+
+        let package_dir = checkout_git_dependency(&cache_root, &url, None, Some("packages/my_lib"), false).unwrap();
+        assert!(fs::read_to_string(package_dir.join(MANIFEST_FILE_NAME)).unwrap().contains("0.1.0"));
+
+        commit_package(&work, "0.2.0", "v0.2.0");
+        let package_dir = checkout_git_dependency(&cache_root, &url, None, Some("packages/my_lib"), false).unwrap();
+        assert!(fs::read_to_string(package_dir.join(MANIFEST_FILE_NAME)).unwrap().contains("0.2.0"));
+    }
+
+    #[test]
+    fn test_repository_status() {
+        let temp_dir = tempdir().unwrap();
+        let (_, work) = setup_bare_repository(temp_dir.path());
+        let package_dir = work.join("packages/my_lib");
+
+        let status = repository_status(&package_dir).unwrap().unwrap();
+        assert_eq!(status.path_in_repository, "packages/my_lib");
+        assert_eq!(status.commit.len(), 40);
+        assert!(status.changed_files.is_empty());
+
+        fs::write(package_dir.join(MANIFEST_FILE_NAME), "[package]\nname = \"changed\"\n").unwrap();
+        fs::create_dir_all(package_dir.join("src")).unwrap();
+        fs::write(package_dir.join("src/lib.nr"), "").unwrap();
+        fs::write(work.join("outside.txt"), "").unwrap();
+
+        let status = repository_status(&package_dir).unwrap().unwrap();
+        assert_eq!(status.changed_files, vec![MANIFEST_FILE_NAME.to_string(), "src/lib.nr".to_string()]);
+
+        assert!(repository_status(temp_dir.path()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_repository_status_renames_of_short_paths() {
+        let temp_dir = tempdir().unwrap();
+        let (_, work) = setup_bare_repository(temp_dir.path());
+        let package_dir = work.join("packages/my_lib");
+        for file in ["a", "b.nr"] {
+            fs::write(package_dir.join(file), file).unwrap();
+        }
+        git(&work, &["add", "-A"]);
+        git(&work, &["commit", "-m", "files"]);
+
+        git(&work, &["mv", "packages/my_lib/a", "packages/my_lib/renamed"]);
+        fs::remove_file(package_dir.join("b.nr")).unwrap();
+
+        let mut status = repository_status(&package_dir).unwrap().unwrap();
+        status.deleted_files.sort();
+        assert_eq!(status.changed_files, vec!["renamed".to_string()]);
+        assert_eq!(status.deleted_files, vec!["a".to_string(), "b.nr".to_string()]);
+
+        // short paths relative to the repository root are parsed too
+        git(&work, &["reset", "--hard"]);
+        fs::write(work.join("x"), "x").unwrap();
+        git(&work, &["add", "-A"]);
+        git(&work, &["commit", "-m", "x"]);
+        git(&work, &["mv", "x", "y"]);
+        fs::write(work.join("README.md"), "changed").unwrap();
+        let status = repository_status(&work).unwrap().unwrap();
+        assert_eq!(status.changed_files, vec!["y".to_string(), "README.md".to_string()]);
+        assert_eq!(status.deleted_files, vec!["x".to_string()]);
+    }
+
+    #[test]
+    fn test_checkout_git_dependency_errors() {
+        let temp_dir = tempdir().unwrap();
+        let (url, _) = setup_bare_repository(temp_dir.path());
+        let cache_root = temp_dir.path().join("deps");
+
+        let missing_directory = checkout_git_dependency(&cache_root, &url, Some("v0.1.0"), Some("packages/other"), false);
+        assert!(missing_directory.unwrap_err().to_string().contains("does not contain"));
+
+        let missing_tag = checkout_git_dependency(&cache_root, &url, Some("v9.9.9"), None, false);
+        assert!(missing_tag.is_err());
+        assert!(!get_git_checkout_dir(cache_root.clone(), &url, Some("v9.9.9")).exists());
+
+        let offline = checkout_git_dependency(&cache_root, &url, Some("v0.2.0"), None, true);
+        assert!(offline.unwrap_err().to_string().contains("offline mode"));
+
+        for tag in ["..", "../../outside", "v1\\..\\..", "--upload-pack=evil"] {
+            let invalid_tag = checkout_git_dependency(&cache_root, &url, Some(tag), None, false);
+            assert!(invalid_tag.unwrap_err().to_string().contains("Invalid tag"), "tag {}", tag);
+        }
+        assert!(!cache_root.join(crate::config::GIT_CHECKOUTS_FOLDER_NAME).join("outside").exists());
+
+        for directory in ["..", "packages/../../outside", "/etc"] {
+            let invalid_directory = checkout_git_dependency(&cache_root, &url, Some("v0.1.0"), Some(directory), false);
+            assert!(invalid_directory.unwrap_err().to_string().contains("Invalid directory"), "directory {}", directory);
+        }
+
+        // the url is never read as a git option
+        let option_url = checkout_git_dependency(&cache_root, "--upload-pack=touch injected", None, None, false);
+        assert!(option_url.is_err());
+        assert!(!std::env::current_dir().unwrap().join("injected").exists());
+    }
+
+    #[test]
+    fn test_checkout_git_dependency_tag_with_slash() {
+        let temp_dir = tempdir().unwrap();
+        let (url, work) = setup_bare_repository(temp_dir.path());
+        commit_package(&work, "1.0.0", "release/1.0");
+        let cache_root = temp_dir.path().join("deps");
+
+        let package_dir = checkout_git_dependency(&cache_root, &url, Some("release/1.0"), Some("packages/my_lib"), false).unwrap();
+
+        assert!(package_dir.starts_with(cache_root.join(crate::config::GIT_CHECKOUTS_FOLDER_NAME)));
+        assert!(fs::read_to_string(package_dir.join(MANIFEST_FILE_NAME)).unwrap().contains("1.0.0"));
+    }
+}
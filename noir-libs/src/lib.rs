@@ -1,7 +1,14 @@
+pub mod cache;
 pub mod config;
 pub mod filesystem;
+pub mod git;
 pub mod manifest;
+pub mod metadata;
 pub mod ops;
 pub mod path;
+pub mod policy;
+pub mod signing;
 pub mod tar;
+pub mod trust;
 pub mod api;
+pub mod workspace;
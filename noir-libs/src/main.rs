@@ -1,15 +1,26 @@
 use anyhow::bail;
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand};
 use colored::Colorize;
 use indoc::formatdoc;
-use noir_libs::config::PACKAGING_OUTPUT_FOLDER_PATH;
+use noir_libs::config::{MANIFEST_FILE_NAME, PACKAGING_OUTPUT_FOLDER_PATH, VENDOR_CHECKSUM_FILE_NAME};
 use noir_libs::ops::add::add;
-use noir_libs::ops::package::package::package;
-use noir_libs::ops::publish::publish;
+use noir_libs::ops::package::package::{list_package_files, package, PackageOptions};
+use noir_libs::ops::publish::{get_internal_dependencies, package_order, publish, PublishOptions};
 use noir_libs::ops::remove;
 use noir_libs::ops::yank::yank;
-use std::io;
+use std::path::{Path, PathBuf};
 use noir_libs::ops::fetch::fetch;
+use noir_libs::cache::format_size;
+use noir_libs::ops::cache;
+use noir_libs::ops::options::ResolveOptions;
+use noir_libs::ops::migrate::migrate;
+use noir_libs::ops::audit::{audit, AuditReport, Severity};
+use noir_libs::ops::sbom::{sbom, SbomFormat};
+use noir_libs::ops::licenses::licenses;
+use noir_libs::ops::vendor::{check_vendor, vendor, VendorOptions};
+use noir_libs::signing::{default_signing_key_path, encode_public_key, generate_signing_key, read_signing_key};
+use noir_libs::workspace::{find_project, PackageSelection, Project};
+use ed25519_dalek::SigningKey;
 
 /// A CLI package manager for Noir | noir-libs.org
 #[derive(Parser)]
@@ -17,6 +28,18 @@ use noir_libs::ops::fetch::fetch;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Run without accessing the network, use only packages from the cache
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Fail if the project manifest would have to be changed
+    #[arg(long, global = true)]
+    locked: bool,
+
+    /// Equivalent to specifying both --locked and --offline
+    #[arg(long, global = true)]
+    frozen: bool,
 }
 
 
@@ -26,22 +49,59 @@ enum Commands {
     Add {
         /// Packages in the format "package@version" or "package" for the latest version
         packages: Vec<String>,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
     },
     /// Removes packages from the project
     Remove {
         /// Names of the packages to remove
         package_names: Vec<String>,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
     },
 
     /// Packages a local package into distributable tarball.
     Package {
-        /// Skip "build successful" confirmation prompt before publishing
-        #[arg(short = 'f', long = "force")]
+        /// Skip verifying that the packaged tarball builds with "nargo check"
+        #[arg(long)]
+        no_verify: bool,
+        /// Deprecated: the confirmation prompt it skipped was removed, packages are still verified
+        #[arg(short = 'f', long, hide = true)]
         force: bool,
+        /// Print the files which would be packaged without writing the tarball
+        #[arg(long)]
+        list: bool,
+        /// Package files which likely contain secrets (private keys, tokens, environment files)
+        #[arg(long)]
+        allow_sensitive: bool,
+        /// Package files with uncommitted changes
+        #[arg(long)]
+        allow_dirty: bool,
+        #[command(flatten)]
+        signing: SigningArgs,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
     },
 
     /// Package and publish local package tarball to the remote registry.
-    Publish {},
+    Publish {
+        /// Perform all checks and packaging, but do not upload the package
+        #[arg(long)]
+        dry_run: bool,
+        /// Skip verifying that the packaged tarball builds with "nargo check"
+        #[arg(long)]
+        no_verify: bool,
+        /// Publish files which likely contain secrets (private keys, tokens, environment files)
+        #[arg(long)]
+        allow_sensitive: bool,
+        /// Publish files with uncommitted changes
+        #[arg(long)]
+        allow_dirty: bool,
+        #[command(flatten)]
+        signing: SigningArgs,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+    },
 
     /// Yank a package version (disable from being automatically downloaded). You must be owner of the package.
     Yank {
@@ -51,14 +111,169 @@ enum Commands {
 
     /// Download all dependencies for a project
     Fetch {
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+    },
+
+    /// Replace Aztec git dependencies with packages from the registry
+    Migrate {
+        /// TOML file with additional mappings of git repository directories to registry packages
+        #[arg(long)]
+        mapping: Option<PathBuf>,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+    },
+
+    /// Check resolved dependencies against the security advisory database
+    Audit {
+        /// JSON advisory database, the database of the registry is used by default
+        #[arg(long, value_name = "PATH")]
+        db: Option<PathBuf>,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+    },
+
+    /// List licenses of the resolved dependencies and check them against the license policy
+    Licenses {
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+    },
+
+    /// Write a software bill of materials of the resolved dependencies
+    Sbom {
+        /// SBOM format: cyclonedx or spdx
+        #[arg(long, default_value = "cyclonedx")]
+        format: SbomFormat,
+        /// Write the SBOM to a file instead of the standard output
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+    },
+
+    /// Copy all resolved dependencies into a folder for offline builds
+    Vendor {
+        /// The vendor folder, meant to be committed
+        dir: PathBuf,
+        /// Only check that vendored files match the checksum manifest
+        #[arg(long)]
+        check: bool,
+        /// Replace the vendor folder even if vendored files were changed since vendoring
+        #[arg(long)]
+        force: bool,
+        #[command(flatten)]
+        workspace: WorkspaceArgs,
+    },
+
+    /// Manage packages stored in the project dependencies folder
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommands,
+    },
+
+    /// Manage the key used for signing packages
+    Key {
+        #[command(subcommand)]
+        command: KeyCommands,
+    },
+}
+
+/// Selection of workspace members a command operates on
+#[derive(Args)]
+struct WorkspaceArgs {
+    /// Run the command for all workspace members
+    #[arg(long)]
+    workspace: bool,
+
+    /// Run the command for the given workspace member only
+    #[arg(short = 'p', long = "package", value_name = "MEMBER", conflicts_with = "workspace")]
+    package: Option<String>,
+}
+
+impl WorkspaceArgs {
+    /// Finds the packages selected by the arguments in the current directory, exits on failure
+    fn project(&self) -> Project {
+        let selection = PackageSelection { workspace: self.workspace, package: self.package.clone() };
+        let current_dir = std::env::current_dir().expect("Unable to find current folder");
+        match find_project(&current_dir, &selection) {
+            Ok(project) => project,
+            Err(e) => {
+                println!("{}", format!("Error: {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Signing of packaged tarballs
+#[derive(Args)]
+struct SigningArgs {
+    /// Sign the package with the default signing key
+    #[arg(long)]
+    sign: bool,
+
+    /// Sign the package with the given signing key
+    #[arg(long, value_name = "PATH")]
+    signing_key: Option<PathBuf>,
+}
+
+impl SigningArgs {
+    /// Reads the signing key if signing was requested, exits on failure
+    fn signing_key(&self) -> Option<SigningKey> {
+        if !self.sign && self.signing_key.is_none() {
+            return None;
+        }
+        let result = match &self.signing_key {
+            Some(path) => read_signing_key(path),
+            None => default_signing_key_path().and_then(|path| read_signing_key(&path)),
+        };
+        match result {
+            Ok(key) => Some(key),
+            Err(e) => {
+                println!("{}", format!("Error: {:#}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum KeyCommands {
+    /// Generate a new signing key and print its public key
+    Generate {
+        /// Where to save the key, defaults to NOIR_LIBS_SIGNING_KEY or the user config folder
+        #[arg(long, value_name = "PATH")]
+        output: Option<PathBuf>,
+        /// Replace an existing key
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the public key of a signing key
+    Public {
+        /// The signing key, defaults to NOIR_LIBS_SIGNING_KEY or the user config folder
+        #[arg(long, value_name = "PATH")]
+        key: Option<PathBuf>,
     },
 }
 
+#[derive(Subcommand)]
+enum CacheCommands {
+    /// List cached packages and their sizes
+    List,
+    /// Show the total size of the cache
+    Size,
+    /// Remove all cached packages
+    Clean,
+    /// Remove cached package versions and archives which are not used by the project
+    Prune,
+}
+
 fn main() {
     let cli = Cli::parse();
+    let options = ResolveOptions::new(cli.offline, cli.locked, cli.frozen);
 
     match &cli.command {
-        Commands::Add { packages } => {
+        Commands::Add { packages, workspace } => {
             if packages.is_empty() {
                 Cli::command()
                     .find_subcommand_mut("add")
@@ -67,12 +282,13 @@ fn main() {
                     .unwrap();
                 std::process::exit(1);
             }
+            let project = workspace.project();
             for package in packages {
                 let (package_name, version) = split_package_to_name_and_version(package);
-                add_package(package_name, version);
+                add_package(&project, package_name, version, &options);
             }
         }
-        Commands::Remove { package_names } => {
+        Commands::Remove { package_names, workspace } => {
             if package_names.is_empty() {
                 Cli::command()
                     .find_subcommand_mut("remove")
@@ -81,44 +297,77 @@ fn main() {
                     .unwrap();
                 std::process::exit(1);
             }
+            let project = workspace.project();
             for package_name in package_names {
-                remove_package(package_name);
+                remove_package(&project, package_name, &options);
             }
         }
-        Commands::Publish {} => {
-            match publish() {
+        Commands::Publish { dry_run, no_verify, allow_sensitive, allow_dirty, signing, workspace } => {
+            let publish_options = PublishOptions {
+                dry_run: *dry_run,
+                verify: !no_verify,
+                resolve: options,
+                allow_sensitive: *allow_sensitive,
+                allow_dirty: *allow_dirty,
+                signing_key: signing.signing_key(),
+                workspace: workspace.workspace,
+            };
+            match publish(&workspace.project(), &publish_options) {
                 Ok(result_message) => println!("{}", result_message.green().bold()),
                 Err(e) => {
                     println!("{}", format!("Error: {}", e).red().bold());
+                    std::process::exit(1);
                 }
             }
-            std::process::exit(1);
         }
-        Commands::Package { force } => {
-            if !force {
-                let info = formatdoc! {
-                    "This command packages your project to a tarball which later can be published to a remote registry.
-                     noir-libs is not building a project on your behalf.
-                     Please assure your project builds successfully (with \"nargo build\") before you package your project.
-
-                     Does your project builds successfully (confirm with \"y\")?"};
-                println!("{}", info.yellow().bold());
-                let mut input = String::new();
-                io::stdin().read_line(&mut input).unwrap();
-                if input.trim() != "y" {
-                    println!("{}", "Operation cancelled.".yellow().bold());
-                    return;
+        Commands::Package { no_verify, force, list, allow_sensitive, allow_dirty, signing, workspace } => {
+            if *force {
+                println!("{}", "Warning: --force is deprecated and has no effect. Use --no-verify to skip the build verification.".yellow());
+            }
+            let project = workspace.project();
+            if *list {
+                if let Err(e) = list_packages_files(&project, *allow_sensitive) {
+                    println!("{}", format!("Error: {}", e).red().bold());
+                    std::process::exit(1);
                 }
+                return;
             }
-            let manifest_folder = std::env::current_dir().unwrap();
-            let dst_folder = std::env::current_dir().unwrap().join(PACKAGING_OUTPUT_FOLDER_PATH);
-            match package(&manifest_folder, &dst_folder) {
-                Ok(packaged_tarball) => println!("{}", format!("Successfully packaged. Tarball path: {}", packaged_tarball.tarball_path).green().bold()),
+            let package_dirs = match package_order(&project) {
+                Ok(package_dirs) => package_dirs,
                 Err(e) => {
                     println!("{}", format!("Error: {}", e).red().bold());
+                    std::process::exit(1);
+                }
+            };
+            let signing_key = signing.signing_key();
+            let mut failed = false;
+            let mut packaged_tarballs = Vec::new();
+            for manifest_folder in &package_dirs {
+                let dst_folder = manifest_folder.join(PACKAGING_OUTPUT_FOLDER_PATH);
+                let result = get_internal_dependencies(&project, manifest_folder)
+                    .and_then(|internal_dependencies| package(manifest_folder, &dst_folder, &PackageOptions {
+                        internal_dependencies,
+                        verify: !no_verify,
+                        resolve: options,
+                        allow_sensitive: *allow_sensitive,
+                        allow_dirty: *allow_dirty,
+                        signing_key: signing_key.clone(),
+                        local_packages: packaged_tarballs.clone(),
+                    }));
+                match result {
+                    Ok(packaged_tarball) => {
+                        println!("{}", format!("Successfully packaged. Tarball path: {}", packaged_tarball.tarball_path).green().bold());
+                        packaged_tarballs.push(packaged_tarball);
+                    }
+                    Err(e) => {
+                        println!("{}", format!("Error: {}", e).red().bold());
+                        failed = true;
+                    }
                 }
             }
-            std::process::exit(1);
+            if failed {
+                std::process::exit(1);
+            }
         }
         Commands::Yank { package } => {
             match split_package_to_name_and_version_with_validation(package) {
@@ -128,39 +377,269 @@ fn main() {
                             let result_message = formatdoc! { "Successfully yanked {} {} package version.", &package_name, &version };
                             println!("{}", result_message.green().bold())
                         },
-                        Err(e) => { println!("{}", format!("Error: {}", e).red().bold()); }
+                        Err(e) => {
+                            println!("{}", format!("Error: {}", e).red().bold());
+                            std::process::exit(1);
+                        }
                     }
                 },
-                Err(e) => { println!("{}", format!("Error: {}", e).red().bold()); }
+                Err(e) => {
+                    println!("{}", format!("Error: {}", e).red().bold());
+                    std::process::exit(1);
+                }
             }
-            std::process::exit(1);
         },
-        Commands::Fetch {} => {
-            match fetch() {
+        Commands::Fetch { workspace } => {
+            match fetch(&workspace.project(), &options) {
                 Ok(_) => {
                     println!("{}", "Downloaded all project dependencies.".green().bold());
                 }
                 Err(e) => {
                     println!("{}", format!("Error: {}", e).red().bold());
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Migrate { mapping, workspace } => {
+            match migrate(&workspace.project(), mapping.as_deref(), &options) {
+                Ok(report) => {
+                    for dependency in &report.migrated {
+                        println!("Migrated {} to {}@{}", dependency.name, dependency.package, dependency.version);
+                    }
+                    for (name, reason) in &report.skipped {
+                        println!("{}", format!("Skipped {}: {}", name, reason).yellow());
+                    }
+                    println!("{}", format!("Migrated {} git dependencies.", report.migrated.len()).green().bold());
+                }
+                Err(e) => {
+                    println!("{}", format!("Error: {}", e).red().bold());
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Audit { db, workspace } => {
+            match audit(&workspace.project(), db.as_deref(), &options) {
+                Ok(report) => {
+                    print_audit_report(&report);
+                    if !report.findings.is_empty() {
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    println!("{}", format!("Error: {:#}", e).red().bold());
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Licenses { workspace } => {
+            match licenses(&workspace.project()) {
+                Ok(report) => {
+                    for (license, packages) in &report.groups {
+                        println!("{} ({}): {}", license.bold(), packages.len(), packages.join(", "));
+                    }
+                    for violation in &report.violations {
+                        println!("{}", format!("Error: {}", violation).red().bold());
+                    }
+                    if !report.violations.is_empty() {
+                        std::process::exit(1);
+                    }
+                }
+                Err(e) => {
+                    println!("{}", format!("Error: {}", e).red().bold());
+                    std::process::exit(1);
                 }
             }
         }
+        Commands::Sbom { format, output, workspace } => {
+            let result = sbom(&workspace.project(), *format).and_then(|document| match output {
+                Some(path) => {
+                    std::fs::write(path, document)?;
+                    println!("{}", format!("SBOM written to {}", path.display()).green().bold());
+                    Ok(())
+                }
+                None => {
+                    println!("{}", document);
+                    Ok(())
+                }
+            });
+            if let Err(e) = result {
+                println!("{}", format!("Error: {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+        Commands::Vendor { dir, check, force, workspace } => {
+            if let Err(e) = run_vendor_command(&workspace.project(), dir, *check, *force, &options) {
+                println!("{}", format!("Error: {:#}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+        Commands::Cache { command } => {
+            if let Err(e) = run_cache_command(command) {
+                println!("{}", format!("Error: {}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+        Commands::Key { command } => {
+            if let Err(e) = run_key_command(command) {
+                println!("{}", format!("Error: {:#}", e).red().bold());
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+fn print_audit_report(report: &AuditReport) {
+    for warning in &report.warnings {
+        println!("{}", format!("Warning: {}", warning).yellow());
+    }
+    for finding in &report.findings {
+        let advisory = &finding.advisory;
+        let severity = match advisory.severity {
+            Severity::Critical | Severity::High => advisory.severity.to_string().red().bold(),
+            Severity::Medium => advisory.severity.to_string().yellow().bold(),
+            Severity::Low => advisory.severity.to_string().normal(),
+        };
+        println!("{} {} {}@{}: {}", severity, advisory.id, finding.package, finding.version, advisory.title);
+        if let Some(patched) = &advisory.patched {
+            println!("  Patched versions: {}", patched);
+        }
+        if let Some(url) = &advisory.url {
+            println!("  {}", url);
+        }
+    }
+    for (finding, reason) in &report.ignored {
+        println!("Ignored {} {}@{}: {}", finding.advisory.id, finding.package, finding.version, reason.as_deref().unwrap_or("no reason given"));
+    }
+    let summary = format!("Audited {} packages, found {} advisories ({} ignored).", report.audited, report.findings.len(), report.ignored.len());
+    if report.findings.is_empty() {
+        println!("{}", summary.green().bold());
+    } else {
+        println!("{}", summary.red().bold());
+    }
+}
+
+fn run_vendor_command(project: &Project, dir: &Path, check: bool, force: bool, options: &ResolveOptions) -> anyhow::Result<()> {
+    let vendor_dir = std::path::absolute(dir)?;
+    if check {
+        let drift = check_vendor(&vendor_dir)?;
+        if !drift.is_empty() {
+            bail!("Vendored files do not match {}:\n{}", VENDOR_CHECKSUM_FILE_NAME, drift.join("\n"));
+        }
+        println!("{}", "Vendored files match the checksums.".green().bold());
+        return Ok(());
+    }
+    let report = vendor(project, &vendor_dir, &VendorOptions { resolve: *options, force })?;
+    for manifest in &report.rewritten_manifests {
+        println!("Dependencies of {} now point to {}", manifest.display(), vendor_dir.display());
+    }
+    println!("{}", format!("Vendored {} packages into {}.", report.packages.len(), vendor_dir.display()).green().bold());
+    Ok(())
+}
+
+fn run_key_command(command: &KeyCommands) -> anyhow::Result<()> {
+    match command {
+        KeyCommands::Generate { output, force } => {
+            let path = match output {
+                Some(path) => path.clone(),
+                None => default_signing_key_path()?,
+            };
+            let key = generate_signing_key(&path, *force)?;
+            println!("{}", format!("Signing key saved to {}", path.display()).green().bold());
+            println!("Public key: {}", encode_public_key(&key.verifying_key()));
+        }
+        KeyCommands::Public { key } => {
+            let path = match key {
+                Some(path) => path.clone(),
+                None => default_signing_key_path()?,
+            };
+            println!("{}", encode_public_key(&read_signing_key(&path)?.verifying_key()));
+        }
+    }
+    Ok(())
+}
+
+fn run_cache_command(command: &CacheCommands) -> anyhow::Result<()> {
+    // the dependencies folder is shared by all workspace members
+    let project = find_project(&std::env::current_dir()?, &PackageSelection::default())?;
+    match command {
+        CacheCommands::List => {
+            let entries = cache::list(&project)?;
+            if entries.is_empty() {
+                println!("{}", "Cache is empty.".yellow().bold());
+            }
+            let mut total = 0;
+            for entry in entries {
+                total += entry.size;
+                println!("{}@{} {}", entry.package.name, entry.package.version, format_size(entry.size));
+            }
+            println!("{}", format!("Total: {}", format_size(total)).green().bold());
+        }
+        CacheCommands::Size => {
+            println!("{}", format!("Cache size: {}", format_size(cache::size(&project)?)).green().bold());
+        }
+        CacheCommands::Clean => {
+            let freed = cache::clean(&project)?;
+            println!("{}", format!("Cache cleaned. Freed {}.", format_size(freed)).green().bold());
+        }
+        CacheCommands::Prune => {
+            let result = cache::prune(&project)?;
+            for package in &result.removed_packages {
+                println!("Removed unused package {}@{}", package.name, package.version);
+            }
+            for archive in &result.removed_archives {
+                println!("Removed archive {}", archive.display());
+            }
+            println!("{}", format!("Cache pruned. Freed {}.", format_size(result.freed_bytes)).green().bold());
+        }
+    }
+    Ok(())
+}
+
+fn list_packages_files(project: &Project, allow_sensitive: bool) -> anyhow::Result<()> {
+    for manifest_folder in &project.packages {
+        let internal_dependencies = get_internal_dependencies(project, manifest_folder)?;
+        let files = list_package_files(manifest_folder, &PackageOptions { internal_dependencies, allow_sensitive, ..PackageOptions::default() })?;
+        if project.packages.len() > 1 {
+            println!("{}", manifest_folder.display().to_string().bold());
+        }
+        let mut total = 0;
+        for file in &files {
+            total += file.size;
+            println!("{} ({})", file.path.display(), format_size(file.size));
+        }
+        println!("{}", format!("{} files, {} total", files.len(), format_size(total)).green().bold());
     }
+    Ok(())
 }
 
-fn add_package(package_name: &str, version: &str) {
-    match add(package_name, version) {
+fn add_package(project: &Project, package_name: &str, version: &str, options: &ResolveOptions) {
+    match add(project, package_name, version, options) {
         Ok(ver) =>
             println!("{}", format!("Successfully installed package {}@{} and updated configuration!",  package_name, ver).green().bold()),
         Err(e) => {
-            println!("{}", format!("Error: {}", e).red().bold())
+            println!("{}", format!("Error: {}", e).red().bold());
+            std::process::exit(1);
         }
     };
 }
 
-fn remove_package(package_name: &str) {
-    remove::remove(package_name);
-    println!("{}", format!("Successfully removed package {}",  package_name).green().bold());
+fn remove_package(project: &Project, package_name: &str, options: &ResolveOptions) {
+    if options.locked {
+        println!("{}", format!("Error: Removing {} would modify {}, but --locked or --frozen was passed", package_name, MANIFEST_FILE_NAME).red().bold());
+        std::process::exit(1);
+    }
+    match remove::remove(project, package_name) {
+        Ok(removed) => {
+            println!("{}", format!("Successfully removed package {}",  package_name).green().bold());
+            for cached_package in removed {
+                println!("Removed unused package {}@{}", cached_package.name, cached_package.version);
+            }
+        }
+        Err(e) => {
+            println!("{}", format!("Error: {}", e).red().bold());
+            std::process::exit(1);
+        }
+    }
 }
 
 fn split_package_to_name_and_version(package: &String) -> (&str, &str) {
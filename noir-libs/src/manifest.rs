@@ -1,15 +1,16 @@
 use std::collections::HashMap;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use serde::Deserialize;
 use std::fmt;
 use std::path::{Path, PathBuf};
 use toml_edit::DocumentMut;
 
-use crate::config::MANIFEST_FILE_NAME;
+use crate::config::{DEPENDENCIES_FOLDER_NAME, MANIFEST_FILE_NAME};
 
 #[derive(Debug, Deserialize)]
 pub struct Manifest {
     pub package: Package,
+    #[serde(default)]
     pub dependencies: HashMap<String, Dependency>,
 }
 
@@ -26,6 +27,29 @@ pub struct Package {
     pub keywords: Option<Vec<String>>,
     pub documentation: Option<String>,
     pub repository: Option<String>,
+    pub metadata: Option<PackageMetadataTable>,
+}
+
+/// The `[package.metadata]` table, tools keep their configuration in sub-tables
+#[derive(Debug, Default, Deserialize)]
+pub struct PackageMetadataTable {
+    #[serde(rename = "noir-libs")]
+    pub noir_libs: Option<PackagingConfig>,
+}
+
+/// Packaging configuration in the `[package.metadata.noir-libs]` table. Example:
+///
+/// [package.metadata.noir-libs]
+/// include = ["src/**", "README.md"]
+/// exclude = ["src/test/**"]
+#[derive(Debug, Default, Deserialize)]
+pub struct PackagingConfig {
+    /// Gitignore-style globs of files to package, all files are packaged if empty
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Gitignore-style globs of files which are not packaged
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -66,6 +90,9 @@ pub fn read_manifest(project_dir: &PathBuf) -> Result<Manifest> {
     let manifest = get_manifest(&project_dir).with_context(|| format!("Unable to find {} manifest file. Please verify you are in the correct directory.", &MANIFEST_FILE_NAME))?;
     let content = std::fs::read_to_string(manifest.clone()).with_context(|| format!("Cannot read {} manifest file. File {} was found but cannot be read.", &MANIFEST_FILE_NAME, manifest.to_str().unwrap()))?;
     let doc: toml::Value  = toml::from_str(&content).with_context(|| format!("{} manifest file is invalid TOML.", manifest.to_str().unwrap()))?;
+    if doc.get("workspace").is_some() && doc.get("package").is_none() {
+        bail!("{} is a workspace manifest. Run the command inside a workspace member or select members with --workspace or -p <member>.", manifest.display());
+    }
     let manifest: Manifest = doc.try_into().with_context(|| format!("Failed to parse {} manifest file. Assure file has all required properties.", &MANIFEST_FILE_NAME))?;
     Ok(manifest)
 }
@@ -87,9 +114,24 @@ pub fn read_manifest(project_dir: &PathBuf) -> Result<Manifest> {
 /// This function will panic if the manifest file cannot be found, if the file cannot be read,
 /// or if the content is not valid TOML.
 pub fn write_package_dep(manifest_path: &PathBuf, package_name: &str, path: &str) -> PathBuf {
-    let content = std::fs::read_to_string(manifest_path.clone()).expect(format!("Cannot read {} manifest file", &MANIFEST_FILE_NAME).as_str());
-    let mut doc = content.parse::<DocumentMut>().expect("Invalid TOML");
+    try_write_package_dep(manifest_path, package_name, path).unwrap_or_else(|e| panic!("{:#}", e));
+    manifest_path.clone()
+}
 
+/// Writes a package dependency to the specified TOML manifest file, like [`write_package_dep`].
+///
+/// # Errors
+///
+/// Returns an error if the manifest file cannot be read or written, or if the content is not valid TOML.
+pub fn try_write_package_dep(manifest_path: &Path, package_name: &str, path: &str) -> Result<()> {
+    let content = std::fs::read_to_string(manifest_path).with_context(|| format!("Cannot read {} manifest file", manifest_path.display()))?;
+    let mut doc = content.parse::<DocumentMut>().with_context(|| format!("{} is invalid TOML", manifest_path.display()))?;
+    set_package_dep(&mut doc, package_name, path);
+    std::fs::write(manifest_path, doc.to_string()).with_context(|| format!("Cannot write {}", manifest_path.display()))
+}
+
+/// Sets a path dependency in a parsed manifest, replacing an existing dependency with the same name.
+pub fn set_package_dep(doc: &mut DocumentMut, package_name: &str, path: &str) {
     // Ensure the "dependencies" table exists
     let dependencies = doc
         .entry("dependencies")
@@ -99,10 +141,6 @@ pub fn write_package_dep(manifest_path: &PathBuf, package_name: &str, path: &str
     table.get_or_insert("path", path);
     // Assign to the dependencies table
     dependencies[package_name] = toml_edit::Item::Value(toml_edit::Value::InlineTable(table));
-
-    std::fs::write(manifest_path.clone(), doc.to_string()).expect("Cannot write file");
-
-    manifest_path.clone()
 }
 
 /// Retrieves the dependencies and their versions from the specified TOML manifest file.
@@ -180,6 +218,22 @@ fn get_manifest(dir: &Path) -> Option<PathBuf> {
     }
 }
 
+/// Extracts the package name and version from a path dependency pointing to the dependencies folder.
+/// Example: ../../.noir-libs-deps/value_note/0.67.0 -> (value_note, 0.67.0)
+///
+/// # Returns
+///
+/// `None` if the path does not point to a package in the dependencies folder.
+pub fn get_registry_package_from_path(path: &str) -> Option<(String, String)> {
+    let mut segments = path.split(['/', '\\']).filter(|segment| !segment.is_empty()).rev();
+    let version = segments.next()?;
+    let name = segments.next()?;
+    if segments.next()? != DEPENDENCIES_FOLDER_NAME {
+        return None;
+    }
+    Some((name.to_string(), version.to_string()))
+}
+
 fn extract_version_from_path(path: &str) -> Option<String> {
     if path.is_empty() {
         return None; // Return None if the path is empty
@@ -198,31 +252,51 @@ fn extract_version_from_path(path: &str) -> Option<String> {
 ///
 /// # Errors
 ///
-/// This function will panic if the manifest file cannot be found or if the file cannot be read or written.
-pub fn remove_package(dir: PathBuf, package_name: &str) {
-    let manifest = try_find_manifest(&dir).unwrap();
+/// This function returns an error if the manifest file cannot be found, read or written,
+/// or if the package is not a dependency of the project.
+pub fn remove_package(dir: PathBuf, package_name: &str) -> Result<()> {
+    if !remove_package_if_present(dir, package_name)? {
+        bail!("Package {} is not a dependency of the project. Nothing to remove.", package_name);
+    }
+    Ok(())
+}
+
+/// Removes a package from the dependencies in the manifest file if it is a dependency.
+///
+/// # Returns
+///
+/// `false` if the package is not a dependency, the manifest file is not changed then.
+///
+/// # Errors
+///
+/// This function returns an error if the manifest file cannot be found, read, parsed or written.
+pub fn remove_package_if_present(dir: PathBuf, package_name: &str) -> Result<bool> {
+    let manifest = try_find_manifest(&dir).with_context(|| format!("Unable to find {} manifest file. Please verify you are in the correct directory.", &MANIFEST_FILE_NAME))?;
 
     // Read the file content
-    let content = std::fs::read_to_string(&manifest).expect("Cannot read file");
+    let content = std::fs::read_to_string(&manifest).with_context(|| format!("Cannot read {} manifest file", &MANIFEST_FILE_NAME))?;
 
     // Parse the content as TOML
-    let mut doc = content.parse::<DocumentMut>().expect("Invalid TOML");
-
-    // Ensure that the "dependencies" table exists and is mutable
-    if let Some(dependencies) = doc["dependencies"].as_table_mut() {
-        // Remove the specified package from the dependencies
-        dependencies.remove(package_name);
+    let mut doc = content.parse::<DocumentMut>().with_context(|| format!("{} manifest file is invalid TOML.", manifest.display()))?;
+
+    // Remove the specified package from the dependencies
+    let removed = doc
+        .get_mut("dependencies")
+        .and_then(|dependencies| dependencies.as_table_like_mut())
+        .and_then(|dependencies| dependencies.remove(package_name));
+    if removed.is_none() {
+        return Ok(false);
     }
 
     // Write the modified content back to the file
-    std::fs::write(manifest, doc.to_string()).expect("Cannot write file");
+    std::fs::write(&manifest, doc.to_string()).with_context(|| format!("Cannot write {} manifest file", &MANIFEST_FILE_NAME))?;
+    Ok(true)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::fs;
-    use crate::config::DEPENDENCIES_FOLDER_NAME;
 
     #[test]
     fn test_write_package_dep() {
@@ -299,6 +373,23 @@ mod tests {
         temp_dir.close().unwrap();
     }
 
+    #[test]
+    fn test_get_registry_package_from_path() {
+        assert_eq!(get_registry_package_from_path(".noir-libs-deps/value_note/0.67.0"), Some(("value_note".to_string(), "0.67.0".to_string())));
+        assert_eq!(get_registry_package_from_path("../../.noir-libs-deps/aztec/0.67.0/"), Some(("aztec".to_string(), "0.67.0".to_string())));
+        assert_eq!(get_registry_package_from_path("../other_member"), None);
+        assert_eq!(get_registry_package_from_path("0.67.0"), None);
+    }
+
+    #[test]
+    fn test_read_workspace_manifest() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), "[workspace]\nmembers = [\"a\"]\n").unwrap();
+
+        let result = read_manifest(&temp_dir.path().to_path_buf());
+        assert!(result.unwrap_err().to_string().contains("workspace manifest"));
+    }
+
     #[test]
     fn test_try_find_manifest() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -335,7 +426,7 @@ mod tests {
         .unwrap();
 
         // Call the function to test
-        remove_package(project_dir, "my_package");
+        remove_package(project_dir, "my_package").unwrap();
 
         let content = fs::read_to_string(manifest_path).unwrap();
         assert!(!content.contains("my_package"));
@@ -343,4 +434,28 @@ mod tests {
         // Cleanup
         temp_dir.close().unwrap();
     }
+
+    #[test]
+    fn test_remove_package_not_a_dependency() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let project_dir = temp_dir.path().to_path_buf();
+        let manifest_path = project_dir.join(MANIFEST_FILE_NAME);
+
+        // Create a TOML file with a dependency
+        fs::write(
+            &manifest_path,
+            "[dependencies]\nmy_package = { path = \"../../my_package/0.1.0\" }\n",
+        )
+        .unwrap();
+
+        // Call the function to test
+        let result = remove_package(project_dir, "other_package");
+
+        assert!(result.is_err());
+        let content = fs::read_to_string(manifest_path).unwrap();
+        assert!(content.contains("my_package"));
+
+        // Cleanup
+        temp_dir.close().unwrap();
+    }
 }
@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{METADATA_FILE_NAME, MANIFEST_FILE_NAME};
+use crate::manifest::get_registry_package_from_path;
+
+/// Package metadata written by `package` into the tarball next to the manifest
+/// Example:
+///
+/// {
+///   "name": "value_note",
+///   "version": "0.67.0",
+///   "registry": "https://api.noir-libs.org/api/v1",
+///   "dependencies": { "aztec": { "package": "aztec", "version": "0.67.0" } },
+///   "vcs": { "commit": "4f2a...", "path_in_repository": "noir-projects/aztec-nr/value-note" }
+/// }
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageMetadata {
+    pub name: String,
+    pub version: String,
+    /// Registry the dependencies are resolved from
+    pub registry: String,
+    /// Dependencies by their name in the `[dependencies]` table of the manifest
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, DependencyMetadata>,
+    /// Git commit the package was packaged from, not set for packages outside a git repository
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vcs: Option<VcsMetadata>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VcsMetadata {
+    /// Commit SHA
+    pub commit: String,
+    /// Path of the package relative to the repository root
+    pub path_in_repository: String,
+    /// The package was packaged with uncommitted changes (--allow-dirty)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dirty: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DependencyMetadata {
+    /// Name of the package in the registry
+    pub package: String,
+    /// Required version of the package, currently always an exact version
+    pub version: String,
+}
+
+/// Writes the metadata file into the package directory.
+pub fn write_metadata(package_dir: &Path, metadata: &PackageMetadata) -> Result<()> {
+    let path = package_dir.join(METADATA_FILE_NAME);
+    std::fs::write(&path, metadata_content(metadata)?).with_context(|| format!("Cannot write {}", path.display()))
+}
+
+/// The content of the metadata file as written by [`write_metadata`].
+pub fn metadata_content(metadata: &PackageMetadata) -> Result<String> {
+    Ok(serde_json::to_string_pretty(metadata)? + "\n")
+}
+
+/// Reads the metadata file from the package directory.
+///
+/// # Returns
+///
+/// `None` if the package has no metadata file, e.g. it was published before metadata was introduced.
+pub fn read_metadata(package_dir: &Path) -> Result<Option<PackageMetadata>> {
+    let path = package_dir.join(METADATA_FILE_NAME);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let metadata = serde_json::from_str(&content).with_context(|| format!("{} is invalid", path.display()))?;
+    Ok(Some(metadata))
+}
+
+/// Retrieves the registry packages a cached package depends on.
+///
+/// Dependencies are taken from the metadata file. Packages without metadata fall back
+/// to the packages inferred from the path dependencies in the manifest, see [`read_dependency_metadata`].
+///
+/// # Returns
+///
+/// A vector of (package name, version) tuples.
+pub fn read_package_dependencies(package_dir: &Path) -> Result<Vec<(String, String)>> {
+    Ok(read_dependency_metadata(package_dir)?
+        .into_values()
+        .map(|dependency| (dependency.package, dependency.version))
+        .collect())
+}
+
+/// Retrieves the registry dependencies of a package by their name in the `[dependencies]` table.
+///
+/// Dependencies are taken from the metadata file. Packages without metadata fall back to path
+/// dependencies pointing to the dependencies folder (e.g. `.noir-libs-deps/aztec/0.67.0`) or,
+/// once linked in the cache, to a package next to it (e.g. `../../aztec/0.67.0`).
+pub fn read_dependency_metadata(package_dir: &Path) -> Result<BTreeMap<String, DependencyMetadata>> {
+    if let Some(metadata) = read_metadata(package_dir)? {
+        return Ok(metadata.dependencies);
+    }
+    let manifest_path = package_dir.join(MANIFEST_FILE_NAME);
+    if !manifest_path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    let content = std::fs::read_to_string(&manifest_path).with_context(|| format!("Cannot read {}", manifest_path.display()))?;
+    let doc: toml::Value = toml::from_str(&content).with_context(|| format!("{} is invalid TOML", manifest_path.display()))?;
+    let Some(dependencies) = doc.get("dependencies").and_then(|dependencies| dependencies.as_table()) else {
+        return Ok(BTreeMap::new());
+    };
+    Ok(dependencies
+        .iter()
+        .filter_map(|(name, dependency)| {
+            let path = dependency.get("path")?.as_str()?;
+            let (package, version) = get_registry_package_from_path(path).or_else(|| get_linked_package_from_path(path))?;
+            Some((name.clone(), DependencyMetadata { package, version }))
+        })
+        .collect())
+}
+
+// Path written by linking a cached package to a sibling package: ../../<name>/<version>
+fn get_linked_package_from_path(path: &str) -> Option<(String, String)> {
+    match path.split(['/', '\\']).filter(|segment| !segment.is_empty()).collect::<Vec<_>>().as_slice() {
+        ["..", "..", name, version] if *name != ".." && *version != ".." => Some((name.to_string(), version.to_string())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_read_package_dependencies_from_metadata() {
+        let temp_dir = tempdir().unwrap();
+        // the manifest layout is ignored when metadata is present
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), "[dependencies]\nnote = { path = \"somewhere/else\" }\n").unwrap();
+        let metadata = PackageMetadata {
+            name: "my_lib".to_string(),
+            version: "1.0.0".to_string(),
+            registry: "https://registry.example.com".to_string(),
+            dependencies: BTreeMap::from([("note".to_string(), DependencyMetadata { package: "value_note".to_string(), version: "0.67.0".to_string() })]),
+            vcs: Some(VcsMetadata { commit: "0123456789abcdef0123456789abcdef01234567".to_string(), path_in_repository: "libs/my_lib".to_string(), dirty: false }),
+        };
+        write_metadata(temp_dir.path(), &metadata).unwrap();
+
+        assert_eq!(read_metadata(temp_dir.path()).unwrap(), Some(metadata));
+        assert_eq!(read_package_dependencies(temp_dir.path()).unwrap(), vec![("value_note".to_string(), "0.67.0".to_string())]);
+    }
+
+    #[test]
+    fn test_read_package_dependencies_without_metadata() {
+        let temp_dir = tempdir().unwrap();
+        assert!(read_package_dependencies(temp_dir.path()).unwrap().is_empty());
+
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), "[dependencies]\nmy_aztec = { path = \"../../aztec/0.67.0\" }\nlocal = { path = \"../local\" }\n").unwrap();
+
+        assert!(read_metadata(temp_dir.path()).unwrap().is_none());
+        // the package name comes from the path, not from the dependency key
+        assert_eq!(read_package_dependencies(temp_dir.path()).unwrap(), vec![("aztec".to_string(), "0.67.0".to_string())]);
+    }
+
+    #[test]
+    fn test_read_dependency_metadata_from_manifest() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), "[dependencies]\nnote = { path = \".noir-libs-deps/value_note/0.67.0\" }\nlocal = { path = \"../local\" }\n").unwrap();
+
+        let dependencies = read_dependency_metadata(temp_dir.path()).unwrap();
+
+        assert_eq!(dependencies.len(), 1);
+        assert_eq!(dependencies["note"], DependencyMetadata { package: "value_note".to_string(), version: "0.67.0".to_string() });
+    }
+}
@@ -1,26 +1,39 @@
-use std::{env, path::PathBuf};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use crate::api::api::{download_package_api, get_latest_package_version_api};
+use crate::cache::{list_archives, list_cached_packages};
+use crate::metadata::{read_dependency_metadata, read_package_dependencies};
+use crate::policy::read_policy;
+use crate::ops::options::ResolveOptions;
+use crate::workspace::Project;
 use crate::tar::extract_tar_gz;
+use crate::trust::verify_package_trust;
 use crate::{
-    config::MANIFEST_FILE_NAME,
+    config::{MANIFEST_FILE_NAME, POLICY_FILE_NAME, REGISTRY_URL},
     filesystem::prepare_cache_dir,
-    manifest::{get_dependencies, write_package_dep},
+    manifest::{get_dependencies, try_write_package_dep, write_package_dep},
     path::{get_cache_storage, get_package_dir},
 };
 
-pub fn add(package_name: &str, version: &str) -> Result<String, String> {
-    let pwd = env::current_dir().expect("Unable to find current folder");
-    let manifest_path = crate::manifest::try_find_manifest(&pwd).expect(format!("Unable to find {} manifest file", &MANIFEST_FILE_NAME).as_str());
-    let manifest_dir = Path::new(&manifest_path)
-        .parent()
-        .expect("Failed to get manifest parent directory");
-    let cache_root = prepare_cache_dir(&manifest_dir.to_path_buf());
+/// Adds a package to all selected packages of the project.
+///
+/// # Returns
+///
+/// The version of the added package.
+pub fn add(project: &Project, package_name: &str, version: &str, options: &ResolveOptions) -> Result<String, String> {
+    let cache_root = prepare_cache_dir(&project.root);
 
-    let used_version = get_used_version(package_name, version)?;
+    let used_version = get_used_version(cache_root.clone(), package_name, version, options)?;
 
-    store_package(cache_root.clone(), package_name, &used_version, false)?;
-    add_dep_to_manifest(&manifest_path, manifest_dir.to_path_buf(), cache_root, package_name, &used_version);
+    let manifest_paths: Vec<PathBuf> = project.packages.iter().map(|dir| dir.join(MANIFEST_FILE_NAME)).collect();
+    if options.locked && manifest_paths.iter().any(|manifest_path| !get_dependencies(manifest_path.clone()).contains(&(package_name.to_string(), used_version.clone()))) {
+        return Err(format!("Adding {}@{} would modify {}, but --locked or --frozen was passed", package_name, used_version, &MANIFEST_FILE_NAME));
+    }
+
+    store_package(cache_root.clone(), package_name, &used_version, false, options)?;
+    for manifest_path in &manifest_paths {
+        let manifest_dir = manifest_path.parent().expect("Failed to get manifest parent directory");
+        add_dep_to_manifest(manifest_path, manifest_dir.to_path_buf(), cache_root.clone(), package_name, package_name, &used_version);
+    }
 
     Ok(used_version)
 }
@@ -30,49 +43,57 @@ pub fn store_package(
     package_name: &str,
     version: &str,
     fetch_yanked: bool,
+    options: &ResolveOptions,
 ) -> Result<(), String> {
+    let policy = read_policy(cache_root.parent().unwrap_or(&cache_root)).map_err(|e| format!("{:#}", e))?.dependencies;
+    // checked before downloading, the registry is the one packages are downloaded from
+    // and not the registry declared by the package itself
+    policy.check_package(package_name, version)
+        .and_then(|_| policy.check_registry(REGISTRY_URL))
+        .map_err(|reason| format!("Package {}@{} is not allowed by {}: {}", package_name, version, POLICY_FILE_NAME, reason))?;
+
     // Get the package into the cache
-    let package_cache_path = get_to_cache(cache_root.clone(), package_name, version, fetch_yanked)?;
+    let package_cache_path = get_to_cache(cache_root.clone(), package_name, version, fetch_yanked, options)?;
 
     // Resolve sub-dependencies (if any)
-    let package_manifest_path = package_cache_path.join(MANIFEST_FILE_NAME);
-    //println!("Searching deps {:?}", package_manifest_path);
-    if package_manifest_path.is_file() {
-        let sub_deps = get_dependencies(package_manifest_path);
-
-        for (sub_dep_name, sub_dep_version) in sub_deps {
-            //      println!("Found dep {} {}", sub_dep_name, &sub_dep_version);
-            store_package(
-                cache_root.clone(),
-                &sub_dep_name,
-                &sub_dep_version,
-                fetch_yanked,
-            )?;
-        }
+    let sub_deps = read_package_dependencies(&package_cache_path)
+        .map_err(|e| format!("Failed to read dependencies of {}@{}: {}", package_name, version, e))?;
+    for (sub_dep_name, sub_dep_version) in sub_deps {
+        store_package(
+            cache_root.clone(),
+            &sub_dep_name,
+            &sub_dep_version,
+            fetch_yanked,
+            options,
+        )?;
     }
     Ok(())
 }
 
-fn add_dep_to_manifest(
+/// Writes a path dependency pointing to the cached package into the project manifest.
+/// `dependency_name` is the key in the `[dependencies]` table, usually the same as `package_name`.
+pub fn add_dep_to_manifest(
     manifest_path: &PathBuf,
     manifest_dir: PathBuf,
     cache_root: PathBuf,
+    dependency_name: &str,
     package_name: &str,
     version: &str,
 ) {
-    let cached_package_path = get_package_dir(cache_root, package_name, version);
-
-    let relative_path = cached_package_path.strip_prefix(manifest_dir)
-        .unwrap_or(&cached_package_path)
-        .to_path_buf();
+    let relative_path = get_relative_package_path(&manifest_dir, cache_root, package_name, version);
 
     write_package_dep(
         manifest_path,
-        package_name,
+        dependency_name,
         relative_path.to_str().unwrap(),
     );
 }
 
+fn get_relative_package_path(manifest_dir: &Path, cache_root: PathBuf, package_name: &str, version: &str) -> PathBuf {
+    let cached_package_path = get_package_dir(cache_root, package_name, version);
+    pathdiff::diff_paths(&cached_package_path, manifest_dir).unwrap_or(cached_package_path)
+}
+
 /// Retrieves a package from the cache, downloading it if necessary,
 /// and extracts it to the specified directory.
 ///
@@ -82,40 +103,250 @@ fn add_dep_to_manifest(
 /// * `package_name` - The name of the package to retrieve.
 /// * `version` - The version of the package to retrieve.
 /// * `fetch_yanked` - fetch package even if yanked (used for fetch command)
+/// * `options` - in offline mode the package is taken from the cache only
 ///
 /// # Returns
 ///
 /// Returns the path to the cached package.
 fn get_to_cache(cache_root: PathBuf, package_name: &str, version: &str,
-            fetch_yanked: bool) -> Result<PathBuf, String> {
+            fetch_yanked: bool, options: &ResolveOptions) -> Result<PathBuf, String> {
     let package_storage = get_cache_storage(cache_root.clone(), package_name, version);
-    let cached_package_path = get_package_dir(cache_root, package_name, version);
+    let cached_package_path = get_package_dir(cache_root.clone(), package_name, version);
 
-    download_package_api(&package_storage, package_name, version, fetch_yanked)?;
+    if options.offline {
+        if cached_package_path.join(MANIFEST_FILE_NAME).is_file() {
+            return Ok(cached_package_path);
+        }
+        if !package_storage.is_file() {
+            return Err(format!("Package {}@{} is not available in the cache and cannot be downloaded in offline mode", package_name, version));
+        }
+    } else {
+        download_package_api(&package_storage, package_name, version, fetch_yanked)?;
+    }
     extract_tar_gz(&package_storage, &cached_package_path)
         .map_err(|_| "Problem extracting package".to_string())?;
+    // Signature is verified before the manifest is rewritten, an untrusted package is not kept in the cache
+    if let Err(e) = verify_package_trust(&cache_root, &cached_package_path, package_name, version, options.offline) {
+        let _ = std::fs::remove_dir_all(&cached_package_path);
+        return Err(format!("{:#}", e));
+    }
+    link_cached_dependencies(&cache_root, &cached_package_path)?;
 
     Ok(cached_package_path)
 }
 
+/// Rewrites dependencies of an extracted package to point to the sub-dependencies stored next to it
+/// in the cache, so the whole dependency tree compiles with nargo.
+/// Example: aztec = { path = ".noir-libs-deps/aztec/0.67.0" } -> aztec = { path = "../../aztec/0.67.0" }
+pub fn link_cached_dependencies(cache_root: &Path, cached_package_path: &Path) -> Result<(), String> {
+    let manifest_path = cached_package_path.join(MANIFEST_FILE_NAME);
+    if !manifest_path.is_file() {
+        return Ok(());
+    }
+    let dependencies = read_dependency_metadata(cached_package_path).map_err(|e| e.to_string())?;
+    for (dependency_name, dependency) in dependencies {
+        // a downloaded manifest may be malformed, report it instead of panicking
+        let relative_path = get_relative_package_path(cached_package_path, cache_root.to_path_buf(), &dependency.package, &dependency.version);
+        try_write_package_dep(&manifest_path, &dependency_name, relative_path.to_str().unwrap())
+            .map_err(|e| format!("{:#}", e))?;
+    }
+    Ok(())
+}
+
 /// Retrieves the used version of a package.
 ///
 /// This function checks if the requested version is "latest". If so, it fetches
-/// the latest version available for the specified package. Otherwise, it returns
-/// the provided version as is.
+/// the latest version available for the specified package (or the latest cached version
+/// in offline mode). Otherwise, it returns the provided version as is.
 ///
 /// # Arguments
 ///
+/// * `cache_root` - The root directory of the cache.
 /// * `package_name` - The name of the package for which to retrieve the version.
 /// * `version` - The version string, which can be "latest" or a specific version.
+/// * `options` - Resolve options.
 ///
 /// # Returns
 ///
 /// Returns a `String` representing the version of the package.
-fn get_used_version(package_name: &str, version: &str) -> Result<String, String> {
-    if version == "latest" {
-        get_latest_package_version_api(package_name)
-    } else {
+fn get_used_version(cache_root: PathBuf, package_name: &str, version: &str, options: &ResolveOptions) -> Result<String, String> {
+    if version != "latest" {
         Ok(version.to_string())
+    } else if options.offline {
+        get_latest_cached_version(&cache_root, package_name)?
+            .ok_or_else(|| format!("Cannot resolve the latest version of {} in offline mode: no cached versions found", package_name))
+    } else {
+        get_latest_package_version_api(package_name)
+    }
+}
+
+/// Finds the highest version of a package available in the cache, either extracted or as a downloaded archive.
+fn get_latest_cached_version(cache_root: &Path, package_name: &str) -> Result<Option<String>, String> {
+    let extracted = list_cached_packages(cache_root)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|package| package.name == package_name)
+        .map(|package| package.version);
+    let archived = list_archives(cache_root)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|(name, _, _)| name == package_name)
+        .map(|(_, version, _)| version);
+
+    Ok(extracted
+        .chain(archived)
+        .filter_map(|version| semver::Version::parse(&version).ok())
+        .max()
+        .map(|version| version.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DEPENDENCIES_FOLDER_NAME, METADATA_FILE_NAME};
+    use crate::workspace::{find_project, PackageSelection};
+    use std::fs;
+    use tempfile::tempdir;
+
+    const OFFLINE: ResolveOptions = ResolveOptions { offline: true, locked: false };
+
+    #[test]
+    fn test_add_to_workspace_members() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join(MANIFEST_FILE_NAME), "[workspace]\nmembers = [\"crates/a\", \"crates/b\"]\n").unwrap();
+        for member in ["a", "b"] {
+            fs::create_dir_all(root.join("crates").join(member)).unwrap();
+            fs::write(root.join("crates").join(member).join(MANIFEST_FILE_NAME), format!("[package]\nname = \"{}\"\n", member)).unwrap();
+        }
+        let cached_package = root.join(DEPENDENCIES_FOLDER_NAME).join("aztec/0.67.0");
+        fs::create_dir_all(&cached_package).unwrap();
+        fs::write(cached_package.join(MANIFEST_FILE_NAME), "[package]\nname = \"aztec\"\n").unwrap();
+        let project = find_project(root, &PackageSelection { workspace: true, package: None }).unwrap();
+
+        let version = add(&project, "aztec", "latest", &OFFLINE).unwrap();
+
+        assert_eq!(version, "0.67.0");
+        for member in ["a", "b"] {
+            let content = fs::read_to_string(root.join("crates").join(member).join(MANIFEST_FILE_NAME)).unwrap();
+            let expected_path = Path::new("..").join("..").join(DEPENDENCIES_FOLDER_NAME).join("aztec").join("0.67.0");
+            assert!(content.contains(&format!("aztec = {{ path = {:?} }}", expected_path.to_str().unwrap())));
+        }
+        assert!(!root.join("crates/a").join(DEPENDENCIES_FOLDER_NAME).exists());
+    }
+
+    #[test]
+    fn test_store_package_enforces_dependency_policy() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().join(DEPENDENCIES_FOLDER_NAME);
+        for (package, version) in [("aztec", "0.66.0"), ("aztec", "0.67.0")] {
+            let dir = cache_root.join(package).join(version);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join(MANIFEST_FILE_NAME), "[package]\nname = \"aztec\"\n").unwrap();
+        }
+        fs::write(temp_dir.path().join(POLICY_FILE_NAME), "[dependencies]\nbanned-versions = { aztec = [\"0.66.0\"] }\n").unwrap();
+
+        let error = store_package(cache_root.clone(), "aztec", "0.66.0", false, &OFFLINE).unwrap_err();
+
+        assert!(error.contains("Package aztec@0.66.0 is not allowed by noir-libs-policy.toml: version 0.66.0 of aztec is banned"));
+        assert!(store_package(cache_root.clone(), "aztec", "0.67.0", false, &OFFLINE).is_ok());
+
+        // the registry packages are downloaded from is checked, not the one in the package metadata
+        fs::write(temp_dir.path().join(POLICY_FILE_NAME), "[dependencies]\nallowed-registries = [\"https://example.com/api/v1\"]\n").unwrap();
+        fs::write(cache_root.join("aztec/0.67.0").join(METADATA_FILE_NAME), r#"{"name": "aztec", "version": "0.67.0", "registry": "https://example.com/api/v1"}"#).unwrap();
+        let error = store_package(cache_root, "aztec", "0.67.0", false, &OFFLINE).unwrap_err();
+        assert!(error.contains("is not in the allowed registries"));
+    }
+
+    #[test]
+    fn test_get_used_version_offline_picks_latest_cached() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().to_path_buf();
+        fs::create_dir_all(cache_root.join("aztec/0.9.0")).unwrap();
+        fs::create_dir_all(cache_root.join("aztec/0.67.0")).unwrap();
+        fs::write(cache_root.join("aztec_0.70.0.archive"), "").unwrap();
+        fs::write(cache_root.join("value_note_1.0.0.archive"), "").unwrap();
+
+        assert_eq!(get_used_version(cache_root.clone(), "aztec", "latest", &OFFLINE).unwrap(), "0.70.0");
+        assert_eq!(get_used_version(cache_root.clone(), "aztec", "0.9.0", &OFFLINE).unwrap(), "0.9.0");
+        assert!(get_used_version(cache_root, "unknown", "latest", &OFFLINE).is_err());
+    }
+
+    #[test]
+    fn test_store_package_offline() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().to_path_buf();
+        let package_dir = get_package_dir(cache_root.clone(), "value_note", "0.67.0");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(package_dir.join(MANIFEST_FILE_NAME), "[dependencies]\naztec = { path = \"../../aztec/0.67.0\" }\n").unwrap();
+
+        // sub-dependency is missing in the cache
+        let result = store_package(cache_root.clone(), "value_note", "0.67.0", false, &OFFLINE);
+        assert!(result.unwrap_err().contains("aztec@0.67.0 is not available in the cache"));
+
+        // sub-dependency is available as a downloaded archive
+        let package_src = temp_dir.path().join("aztec_src");
+        fs::create_dir_all(&package_src).unwrap();
+        fs::write(package_src.join(MANIFEST_FILE_NAME), "[package]\nname = \"aztec\"\n\n[dependencies]\n").unwrap();
+        crate::tar::create_tar_gz(&package_src, &get_cache_storage(cache_root.clone(), "aztec", "0.67.0")).unwrap();
+        store_package(cache_root.clone(), "value_note", "0.67.0", false, &OFFLINE).unwrap();
+        assert!(get_package_dir(cache_root, "aztec", "0.67.0").join(MANIFEST_FILE_NAME).is_file());
+    }
+
+    #[test]
+    fn test_store_package_links_cached_dependencies() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().join(DEPENDENCIES_FOLDER_NAME);
+        fs::create_dir_all(&cache_root).unwrap();
+        let aztec_dir = get_package_dir(cache_root.clone(), "aztec", "0.67.0");
+        fs::create_dir_all(&aztec_dir).unwrap();
+        fs::write(aztec_dir.join(MANIFEST_FILE_NAME), "[package]\nname = \"aztec\"\n").unwrap();
+        // packaged manifest references dependencies relative to the author's project
+        let package_src = temp_dir.path().join("value_note_src");
+        fs::create_dir_all(&package_src).unwrap();
+        fs::write(package_src.join(MANIFEST_FILE_NAME), "[package]\nname = \"value_note\"\n\n[dependencies]\naztec = { path = \".noir-libs-deps/aztec/0.67.0\" }\n").unwrap();
+        crate::tar::create_tar_gz(&package_src, &get_cache_storage(cache_root.clone(), "value_note", "0.67.0")).unwrap();
+
+        store_package(cache_root.clone(), "value_note", "0.67.0", false, &OFFLINE).unwrap();
+
+        let value_note_dir = get_package_dir(cache_root, "value_note", "0.67.0");
+        let content = fs::read_to_string(value_note_dir.join(MANIFEST_FILE_NAME)).unwrap();
+        let expected_path = Path::new("..").join("..").join("aztec").join("0.67.0");
+        assert!(content.contains(&format!("aztec = {{ path = {:?} }}", expected_path.to_str().unwrap())));
+        assert!(value_note_dir.join(&expected_path).join(MANIFEST_FILE_NAME).is_file());
+    }
+
+    #[test]
+    fn test_link_cached_dependencies_reports_malformed_manifest() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().to_path_buf();
+        let package_dir = get_package_dir(cache_root.clone(), "value_note", "0.67.0");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(package_dir.join(MANIFEST_FILE_NAME), "[package\nname = ").unwrap();
+        fs::write(package_dir.join(METADATA_FILE_NAME), r#"{"name": "value_note", "version": "0.67.0", "registry": "https://example.com/api/v1", "dependencies": {"aztec": {"package": "aztec", "version": "0.67.0"}}}"#).unwrap();
+
+        let error = link_cached_dependencies(&cache_root, &package_dir).unwrap_err();
+
+        assert!(error.contains("is invalid TOML"), "{}", error);
+    }
+
+    #[test]
+    fn test_store_package_resolves_aliased_dependency_without_metadata() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().join(DEPENDENCIES_FOLDER_NAME);
+        let aztec_dir = get_package_dir(cache_root.clone(), "aztec", "0.67.0");
+        fs::create_dir_all(&aztec_dir).unwrap();
+        fs::write(aztec_dir.join(MANIFEST_FILE_NAME), "[package]\nname = \"aztec\"\n").unwrap();
+        let package_src = temp_dir.path().join("value_note_src");
+        fs::create_dir_all(&package_src).unwrap();
+        fs::write(package_src.join(MANIFEST_FILE_NAME), "[package]\nname = \"value_note\"\n\n[dependencies]\nmy_aztec = { path = \".noir-libs-deps/aztec/0.67.0\" }\n").unwrap();
+        crate::tar::create_tar_gz(&package_src, &get_cache_storage(cache_root.clone(), "value_note", "0.67.0")).unwrap();
+
+        // the sub-dependency is looked up as aztec@0.67.0, not as my_aztec@<path>
+        store_package(cache_root.clone(), "value_note", "0.67.0", false, &OFFLINE).unwrap();
+
+        let content = fs::read_to_string(get_package_dir(cache_root, "value_note", "0.67.0").join(MANIFEST_FILE_NAME)).unwrap();
+        let expected_path = Path::new("..").join("..").join("aztec").join("0.67.0");
+        assert!(content.contains(&format!("my_aztec = {{ path = {:?} }}", expected_path.to_str().unwrap())));
     }
 }
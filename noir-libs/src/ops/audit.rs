@@ -0,0 +1,263 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Deserialize;
+use time::Date;
+
+use crate::api::api::get_advisories_api;
+use crate::cache::{resolve_graph, ResolvedPackage};
+use crate::config::{ADVISORY_DB_FILE_NAME, MANIFEST_FILE_NAME};
+use crate::filesystem::prepare_cache_dir;
+use crate::ops::options::ResolveOptions;
+use crate::policy::{read_policy, today, AuditPolicy};
+use crate::workspace::Project;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        write!(f, "{}", severity)
+    }
+}
+
+/// A security advisory for versions of a package. Example:
+///
+/// {
+///   "id": "NOIR-2025-0001",
+///   "package": "value_note",
+///   "title": "Underconstrained note value allows minting",
+///   "severity": "critical",
+///   "affected": ">=0.60.0, <0.67.1",
+///   "patched": ">=0.67.1",
+///   "url": "https://noir-libs.org/advisories/NOIR-2025-0001"
+/// }
+#[derive(Debug, Clone, Deserialize)]
+pub struct Advisory {
+    pub id: String,
+    pub package: String,
+    pub title: String,
+    pub severity: Severity,
+    /// Semver requirement matching the affected versions
+    pub affected: String,
+    /// Semver requirement matching the versions with a fix
+    pub patched: Option<String>,
+    pub url: Option<String>,
+}
+
+/// Advisory database in the JSON format: { "advisories": [...] }
+#[derive(Debug, Deserialize)]
+struct AdvisoryDatabase {
+    #[serde(default)]
+    advisories: Vec<Advisory>,
+}
+
+/// An advisory affecting a resolved package version
+#[derive(Debug, Clone)]
+pub struct AuditFinding {
+    pub package: String,
+    pub version: String,
+    pub advisory: Advisory,
+}
+
+#[derive(Debug, Default)]
+pub struct AuditReport {
+    /// Number of audited package versions
+    pub audited: usize,
+    /// Findings sorted from the most severe
+    pub findings: Vec<AuditFinding>,
+    /// Findings ignored by the policy with the reason
+    pub ignored: Vec<(AuditFinding, Option<String>)>,
+    pub warnings: Vec<String>,
+}
+
+/// Checks all resolved dependencies of the selected packages against the advisory database.
+/// Fails if a dependency cannot be resolved, git dependencies are reported as warnings.
+///
+/// # Arguments
+///
+/// * `project` - The selected packages.
+/// * `db` - JSON advisory database, the database of the registry is used if not set.
+/// * `options` - Resolve options, the cached registry database is used in offline mode.
+pub fn audit(project: &Project, db: Option<&Path>, options: &ResolveOptions) -> Result<AuditReport> {
+    let cache_root = prepare_cache_dir(&project.root);
+    let advisories = load_advisories(&cache_root, db, options)?;
+    let policy = read_policy(&project.root)?;
+    let manifests: Vec<PathBuf> = project.packages.iter().map(|dir| dir.join(MANIFEST_FILE_NAME)).collect();
+    let graph = resolve_graph(&cache_root, &manifests);
+    // a clean report must not hide dependencies which were not audited
+    if !graph.unresolved.is_empty() {
+        anyhow::bail!("Unable to resolve dependencies, run \"noir-libs fetch\" first:\n{}", graph.unresolved.join("\n"));
+    }
+    let mut report = audit_packages(&graph.packages, &advisories, &policy.audit, today())?;
+    report.warnings.extend(graph.git_dependencies.iter().map(|dependency| format!("Git dependency {} was not audited", dependency)));
+    Ok(report)
+}
+
+/// Reads the advisory database from a file, or downloads the registry database and caches it
+/// in the dependencies folder. The cached database is used offline or when the registry is unavailable.
+pub fn load_advisories(cache_root: &Path, db: Option<&Path>, options: &ResolveOptions) -> Result<Vec<Advisory>> {
+    if let Some(db) = db {
+        let content = std::fs::read_to_string(db).with_context(|| format!("Cannot read advisory database {}", db.display()))?;
+        return parse_advisories(&content).with_context(|| format!("Advisory database {} is invalid", db.display()));
+    }
+    let cache_path = cache_root.join(ADVISORY_DB_FILE_NAME);
+    if !options.offline {
+        match get_advisories_api() {
+            Ok(content) => {
+                let advisories = parse_advisories(&content).context("Advisory database of the registry is invalid")?;
+                std::fs::write(&cache_path, content)?;
+                return Ok(advisories);
+            }
+            Err(e) if cache_path.is_file() => {
+                println!("{}", format!("Warning: unable to download advisories, using the cached database: {}", e).yellow());
+            }
+            Err(e) => return Err(e.context("Unable to download advisories")),
+        }
+    }
+    if !cache_path.is_file() {
+        anyhow::bail!("No cached advisory database is available in offline mode. Run the audit online or pass --db.");
+    }
+    let content = std::fs::read_to_string(&cache_path)?;
+    parse_advisories(&content).with_context(|| format!("{} is invalid", cache_path.display()))
+}
+
+fn parse_advisories(content: &str) -> Result<Vec<Advisory>> {
+    let database: AdvisoryDatabase = serde_json::from_str(content)?;
+    Ok(database.advisories)
+}
+
+/// Matches packages against advisories. Ignored advisories are not reported until their ignore entry expires.
+pub fn audit_packages(packages: &[ResolvedPackage], advisories: &[Advisory], policy: &AuditPolicy, today: Date) -> Result<AuditReport> {
+    let mut report = AuditReport { audited: packages.len(), ..AuditReport::default() };
+    for ignore in &policy.ignore {
+        if !ignore.is_active(today)? {
+            report.warnings.push(format!("Ignore of advisory {} expired on {}", ignore.id, ignore.until.as_deref().unwrap_or_default()));
+        }
+    }
+
+    for package in packages {
+        let Ok(version) = semver::Version::parse(&package.version) else {
+            report.warnings.push(format!("Version {} of {} is not a semantic version and was not audited", package.version, package.name));
+            continue;
+        };
+        for advisory in advisories.iter().filter(|advisory| advisory.package == package.name) {
+            let affected = semver::VersionReq::parse(&advisory.affected)
+                .with_context(|| format!("Advisory {} has invalid affected versions {}", advisory.id, advisory.affected))?;
+            if !affected.matches(&version) {
+                continue;
+            }
+            let finding = AuditFinding { package: package.name.clone(), version: package.version.clone(), advisory: advisory.clone() };
+            let ignore = policy.ignore.iter().find(|ignore| ignore.id == advisory.id);
+            match ignore {
+                Some(ignore) if ignore.is_active(today)? => report.ignored.push((finding, ignore.reason.clone())),
+                _ => report.findings.push(finding),
+            }
+        }
+    }
+    report.findings.sort_by(|a, b| b.advisory.severity.cmp(&a.advisory.severity).then_with(|| a.advisory.id.cmp(&b.advisory.id)));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{parse_date, IgnoredAdvisory};
+    use std::fs;
+    use tempfile::tempdir;
+
+    const DATABASE: &str = r#"{
+        "advisories": [
+            { "id": "NOIR-2025-0001", "package": "value_note", "title": "Underconstrained note value", "severity": "critical", "affected": "<0.67.1", "patched": ">=0.67.1" },
+            { "id": "NOIR-2025-0002", "package": "aztec", "title": "Missing range check", "severity": "medium", "affected": ">=0.60.0, <0.66.0" },
+            { "id": "NOIR-2025-0003", "package": "aztec", "title": "Weak nullifier", "severity": "high", "affected": "<0.68.0" }
+        ]
+    }"#;
+
+    fn resolved(name: &str, version: &str) -> ResolvedPackage {
+        ResolvedPackage { name: name.to_string(), version: version.to_string(), dir: PathBuf::new(), dependencies: Vec::new() }
+    }
+
+    #[test]
+    fn test_audit_packages() {
+        let advisories = parse_advisories(DATABASE).unwrap();
+        let packages = [resolved("aztec", "0.67.0"), resolved("value_note", "0.67.0"), resolved("easy_private_state", "0.67.0")];
+
+        let report = audit_packages(&packages, &advisories, &AuditPolicy::default(), parse_date("2025-01-01").unwrap()).unwrap();
+
+        assert_eq!(report.audited, 3);
+        let ids: Vec<&str> = report.findings.iter().map(|finding| finding.advisory.id.as_str()).collect();
+        assert_eq!(ids, vec!["NOIR-2025-0001", "NOIR-2025-0003"]);
+        assert!(report.ignored.is_empty());
+    }
+
+    #[test]
+    fn test_audit_ignores_until_expiry() {
+        let advisories = parse_advisories(DATABASE).unwrap();
+        let packages = [resolved("value_note", "0.67.0")];
+        let policy = AuditPolicy {
+            ignore: vec![IgnoredAdvisory { id: "NOIR-2025-0001".to_string(), until: Some("2025-06-30".to_string()), reason: Some("not used".to_string()) }],
+        };
+
+        let active = audit_packages(&packages, &advisories, &policy, parse_date("2025-06-30").unwrap()).unwrap();
+        let expired = audit_packages(&packages, &advisories, &policy, parse_date("2025-07-01").unwrap()).unwrap();
+
+        assert!(active.findings.is_empty());
+        assert_eq!(active.ignored.len(), 1);
+        assert_eq!(expired.findings.len(), 1);
+        assert_eq!(expired.warnings, vec!["Ignore of advisory NOIR-2025-0001 expired on 2025-06-30".to_string()]);
+    }
+
+    #[test]
+    fn test_audit_vendored_and_unresolved_dependencies() {
+        use crate::config::VENDOR_CHECKSUM_FILE_NAME;
+        use crate::workspace::{find_project, PackageSelection};
+
+        let temp_dir = tempdir().unwrap();
+        let offline = ResolveOptions { offline: true, locked: false };
+        let db = temp_dir.path().join("advisories.json");
+        fs::write(&db, DATABASE).unwrap();
+        let vendored = temp_dir.path().join("vendor/value_note/0.67.0");
+        fs::create_dir_all(&vendored).unwrap();
+        fs::write(vendored.join(MANIFEST_FILE_NAME), "[package]\nname = \"value_note\"\n").unwrap();
+        fs::write(temp_dir.path().join("vendor").join(VENDOR_CHECKSUM_FILE_NAME), "{}").unwrap();
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), "[dependencies]\nvalue_note = { path = \"vendor/value_note/0.67.0\" }\n").unwrap();
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+
+        let report = audit(&project, Some(&db), &offline).unwrap();
+        assert_eq!(report.audited, 1);
+        assert_eq!(report.findings[0].advisory.id, "NOIR-2025-0001");
+
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), "[dependencies]\naztec = { path = \".noir-libs-deps/aztec/0.67.0\" }\n").unwrap();
+        let error = audit(&project, Some(&db), &offline).unwrap_err();
+        assert!(error.to_string().contains("aztec@0.67.0 is not available"));
+    }
+
+    #[test]
+    fn test_load_advisories_offline() {
+        let temp_dir = tempdir().unwrap();
+        let offline = ResolveOptions { offline: true, locked: false };
+        assert!(load_advisories(temp_dir.path(), None, &offline).is_err());
+
+        fs::write(temp_dir.path().join(ADVISORY_DB_FILE_NAME), DATABASE).unwrap();
+        assert_eq!(load_advisories(temp_dir.path(), None, &offline).unwrap().len(), 3);
+
+        let db = temp_dir.path().join("advisories.json");
+        fs::write(&db, r#"{ "advisories": [] }"#).unwrap();
+        assert!(load_advisories(temp_dir.path(), Some(&db), &offline).unwrap().is_empty());
+    }
+}
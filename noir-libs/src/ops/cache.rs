@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+use crate::cache::{disk_usage, list_cached_packages, remove_stale_archives, remove_unreachable_packages, CachedPackage};
+use crate::workspace::Project;
+
+pub struct CacheEntry {
+    pub package: CachedPackage,
+    pub size: u64,
+}
+
+pub struct PruneResult {
+    pub removed_packages: Vec<CachedPackage>,
+    pub removed_archives: Vec<PathBuf>,
+    pub freed_bytes: u64,
+}
+
+/// Lists packages stored in the project dependencies folder together with their size on disk.
+pub fn list(project: &Project) -> anyhow::Result<Vec<CacheEntry>> {
+    list_cached_packages(&project.cache_root())?
+        .into_iter()
+        .map(|package| {
+            let size = disk_usage(&package.dir)?;
+            Ok(CacheEntry { package, size })
+        })
+        .collect()
+}
+
+/// Returns the total size of the project dependencies folder in bytes.
+pub fn size(project: &Project) -> anyhow::Result<u64> {
+    Ok(disk_usage(&project.cache_root())?)
+}
+
+/// Removes the whole project dependencies folder.
+///
+/// # Returns
+///
+/// Number of freed bytes.
+pub fn clean(project: &Project) -> anyhow::Result<u64> {
+    let cache_root = project.cache_root();
+    let freed_bytes = disk_usage(&cache_root)?;
+    if cache_root.exists() {
+        fs::remove_dir_all(&cache_root).with_context(|| format!("Failed to remove {} folder", cache_root.display()))?;
+    }
+    Ok(freed_bytes)
+}
+
+/// Removes package versions which are not referenced by any package of the project (directly or as
+/// sub-dependencies) and package archives which are not needed anymore.
+pub fn prune(project: &Project) -> anyhow::Result<PruneResult> {
+    let cache_root = project.cache_root();
+    let size_before = disk_usage(&cache_root)?;
+    let removed_packages = remove_unreachable_packages(&cache_root, &project.all_manifests)?;
+    let removed_archives = remove_stale_archives(&cache_root, &project.all_manifests)?;
+    let freed_bytes = size_before - disk_usage(&cache_root)?;
+    Ok(PruneResult { removed_packages, removed_archives, freed_bytes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{DEPENDENCIES_FOLDER_NAME, MANIFEST_FILE_NAME};
+    use crate::workspace::{find_project, PackageSelection};
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn project(dir: &Path) -> Project {
+        find_project(dir, &PackageSelection::default()).unwrap()
+    }
+
+    fn setup_project(project_dir: &Path) -> PathBuf {
+        let cache_root = project_dir.join(DEPENDENCIES_FOLDER_NAME);
+        fs::write(
+            project_dir.join(MANIFEST_FILE_NAME),
+            "[dependencies]\naztec = { path = \".noir-libs-deps/aztec/0.67.0\" }\n",
+        ).unwrap();
+        for version in ["0.66.0", "0.67.0"] {
+            fs::create_dir_all(cache_root.join("aztec").join(version)).unwrap();
+            fs::write(cache_root.join("aztec").join(version).join("lib.nr"), "0123456789").unwrap();
+            fs::write(cache_root.join(format!("aztec_{}.archive", version)), "01234").unwrap();
+        }
+        cache_root
+    }
+
+    #[test]
+    fn test_list_and_size() {
+        let temp_dir = tempdir().unwrap();
+        setup_project(temp_dir.path());
+
+        let entries = list(&project(temp_dir.path())).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].package.version, "0.66.0");
+        assert_eq!(entries[0].size, 10);
+        assert_eq!(size(&project(temp_dir.path())).unwrap(), 30);
+    }
+
+    #[test]
+    fn test_prune() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = setup_project(temp_dir.path());
+
+        let result = prune(&project(temp_dir.path())).unwrap();
+
+        assert_eq!(result.removed_packages.len(), 1);
+        assert_eq!(result.removed_packages[0].version, "0.66.0");
+        assert_eq!(result.removed_archives.len(), 1);
+        assert_eq!(result.freed_bytes, 20);
+        assert!(cache_root.join("aztec/0.67.0").is_dir());
+        assert!(!cache_root.join("aztec/0.66.0").exists());
+        assert!(!cache_root.join("aztec_0.67.0.archive").exists());
+    }
+
+    #[test]
+    fn test_clean() {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = setup_project(temp_dir.path());
+
+        assert_eq!(clean(&project(temp_dir.path())).unwrap(), 30);
+        assert!(!cache_root.exists());
+    }
+}
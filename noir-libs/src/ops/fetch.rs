@@ -1,34 +1,63 @@
-use std::path::Path;
+use std::collections::HashSet;
 use colored::Colorize;
-use crate::config::MANIFEST_FILE_NAME;
 use crate::filesystem::prepare_cache_dir;
-use crate::manifest::{read_manifest, Dependency, Manifest};
+use crate::git::checkout_git_dependency;
+use crate::manifest::{get_registry_package_from_path, read_manifest, Dependency, Manifest};
+use crate::config::POLICY_FILE_NAME;
+use crate::ops::options::ResolveOptions;
+use crate::policy::read_policy;
+use crate::workspace::Project;
 
-pub fn fetch() -> anyhow::Result<()> {
-    let current_dir = std::env::current_dir()?;
-    let manifest_path = crate::manifest::try_find_manifest(&current_dir).expect(format!("Unable to find {} manifest file", &MANIFEST_FILE_NAME).as_str());
-    let manifest: Manifest = read_manifest(&current_dir)?;
-    let manifest_dir = Path::new(&manifest_path)
-        .parent()
-        .expect("Failed to get manifest parent directory");
-    let cache_root = prepare_cache_dir(&manifest_dir.to_path_buf());
+/// Downloads dependencies of all selected packages of the project into the shared dependencies folder.
+/// Path dependencies outside the dependencies folder (e.g. other workspace members) are local and skipped.
+pub fn fetch(project: &Project, options: &ResolveOptions) -> anyhow::Result<()> {
+    let cache_root = prepare_cache_dir(&project.root);
+    let policy = read_policy(&project.root)?.dependencies;
 
-    for dependency in manifest.dependencies.values() {
-        match dependency {
-            Dependency::Path { path } => {
-                let path = Path::new(path.as_str());
-                let version = path.file_name().expect("Path is incorrect")
-                    .to_str().expect("Path file name is not valid UTF-8");
-                let package_name = path.parent().expect("Path is incorrect").file_name().expect("Path is incorrect")
-                    .to_str().expect("Path file name is not valid UTF-8");
-                // Fetching packages should include downloading yanked packages for making current dependencies setup working
-                if let Err(e) = crate::ops::add::store_package(cache_root.clone(), package_name, &version, true) {
-                    println!("{}", format!("Fetching dependency {}@{} failed: {}", package_name, version, e).red().bold());
-                }
-            },
-            Dependency::Git { .. } => {}
+    let mut fetched = HashSet::new();
+    let mut failed = 0;
+    let mut total = 0;
+    for package_dir in &project.packages {
+        let manifest: Manifest = read_manifest(package_dir)?;
+        let mut dependencies: Vec<_> = manifest.dependencies.iter().collect();
+        dependencies.sort_by_key(|(name, _)| name.as_str());
+        for (dependency_name, dependency) in dependencies {
+            match dependency {
+                Dependency::Path { path } => {
+                    let Some((package_name, version)) = get_registry_package_from_path(path) else {
+                        continue;
+                    };
+                    if !fetched.insert((package_name.clone(), version.clone())) {
+                        continue;
+                    }
+                    total += 1;
+                    // Fetching packages should include downloading yanked packages for making current dependencies setup working
+                    if let Err(e) = crate::ops::add::store_package(cache_root.clone(), &package_name, &version, true, options) {
+                        println!("{}", format!("Fetching dependency {}@{} failed: {}", package_name, version, e).red().bold());
+                        failed += 1;
+                    }
+                },
+                Dependency::Git { git, tag, directory } => {
+                    if !fetched.insert((format!("{}#{}", git, directory.as_deref().unwrap_or_default()), tag.clone().unwrap_or_default())) {
+                        continue;
+                    }
+                    total += 1;
+                    if let Err(reason) = policy.check_git_dependency(dependency_name, git) {
+                        println!("{}", format!("Git dependency {} is not allowed by {}: {}", dependency_name, POLICY_FILE_NAME, reason).red().bold());
+                        failed += 1;
+                        continue;
+                    }
+                    if let Err(e) = checkout_git_dependency(&cache_root, git, tag.as_deref(), directory.as_deref(), options.offline) {
+                        println!("{}", format!("Fetching git dependency {} failed: {}", dependency_name, e).red().bold());
+                        failed += 1;
+                    }
+                },
+            }
         }
     }
 
+    if failed > 0 {
+        anyhow::bail!("Failed to fetch {} of {} dependencies", failed, total);
+    }
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use spdx::{Expression, LicenseReq};
+
+use crate::cache::resolve_graph;
+use crate::config::{MANIFEST_FILE_NAME, POLICY_FILE_NAME};
+use crate::filesystem::prepare_cache_dir;
+use crate::manifest::read_manifest;
+use crate::policy::{read_policy, LicensePolicy};
+use crate::workspace::Project;
+
+// Group of packages without the license field
+const NO_LICENSE: &str = "(no license)";
+
+/// A resolved dependency with the `license` field of its manifest
+#[derive(Debug, Clone)]
+pub struct LicensedPackage {
+    pub name: String,
+    pub version: String,
+    pub license: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct LicenseReport {
+    /// Packages (name@version) grouped by license
+    pub groups: BTreeMap<String, Vec<String>>,
+    /// Packages violating the license policy with the reason
+    pub violations: Vec<String>,
+}
+
+/// Collects licenses of all resolved dependencies of the selected packages and checks them
+/// against the license policy in `noir-libs-policy.toml`. All dependencies must be fetched.
+/// Git dependencies and dependencies which cannot be resolved have no known license and are violations.
+pub fn licenses(project: &Project) -> Result<LicenseReport> {
+    let cache_root = prepare_cache_dir(&project.root);
+    let policy = read_policy(&project.root)?;
+    let manifests: Vec<PathBuf> = project.packages.iter().map(|dir| dir.join(MANIFEST_FILE_NAME)).collect();
+    let graph = resolve_graph(&cache_root, &manifests);
+    let mut packages = Vec::new();
+    for package in graph.packages {
+        if !package.dir.join(MANIFEST_FILE_NAME).is_file() {
+            bail!("Package {}@{} is not fetched. Run \"noir-libs fetch\" first.", package.name, package.version);
+        }
+        let license = read_manifest(&package.dir)?.package.license;
+        packages.push(LicensedPackage { name: package.name, version: package.version, license });
+    }
+    let mut report = check_licenses(&packages, &policy.licenses);
+    report.violations.extend(graph.git_dependencies.iter().map(|dependency| format!("Git dependency {} has no known license", dependency)));
+    report.violations.extend(graph.unresolved.iter().map(|dependency| format!("Unresolved dependency {} has no known license", dependency)));
+    Ok(report)
+}
+
+/// Groups packages by license and reports packages without a license or with a license not allowed by the policy.
+/// For expressions like `MIT OR Apache-2.0` it is enough if one of the alternatives is allowed.
+pub fn check_licenses(packages: &[LicensedPackage], policy: &LicensePolicy) -> LicenseReport {
+    let mut report = LicenseReport::default();
+    for package in packages {
+        let package_id = format!("{}@{}", package.name, package.version);
+        let license = package.license.as_deref().map(str::trim).filter(|license| !license.is_empty());
+        report.groups.entry(license.unwrap_or(NO_LICENSE).to_string()).or_default().push(package_id.clone());
+
+        let Some(license) = license else {
+            report.violations.push(format!("{} has no license", package_id));
+            continue;
+        };
+        let Ok(expression) = Expression::parse(license) else {
+            report.violations.push(format!("{} has license {} which is not a valid SPDX expression", package_id, license));
+            continue;
+        };
+        if !expression.evaluate(|requirement| is_allowed(requirement, policy)) {
+            report.violations.push(format!("{} has license {} which is not allowed by {}", package_id, license, POLICY_FILE_NAME));
+        }
+    }
+    report
+}
+
+fn is_allowed(requirement: &LicenseReq, policy: &LicensePolicy) -> bool {
+    let matches = |entry: &String| {
+        entry == &requirement.to_string() || requirement.license.id().is_some_and(|id| entry == id.name)
+    };
+    !policy.deny.iter().any(matches) && (policy.allow.is_empty() || policy.allow.iter().any(matches))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn licensed(name: &str, license: Option<&str>) -> LicensedPackage {
+        LicensedPackage { name: name.to_string(), version: "1.0.0".to_string(), license: license.map(str::to_string) }
+    }
+
+    fn policy(allow: &[&str], deny: &[&str]) -> LicensePolicy {
+        LicensePolicy {
+            allow: allow.iter().map(|license| license.to_string()).collect(),
+            deny: deny.iter().map(|license| license.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_groups_by_license() {
+        let packages = [licensed("a", Some("MIT")), licensed("b", Some("Apache-2.0")), licensed("c", Some("MIT")), licensed("d", None)];
+
+        let report = check_licenses(&packages, &LicensePolicy::default());
+
+        assert_eq!(report.groups["MIT"], vec!["a@1.0.0".to_string(), "c@1.0.0".to_string()]);
+        assert_eq!(report.groups["Apache-2.0"], vec!["b@1.0.0".to_string()]);
+        assert_eq!(report.groups[NO_LICENSE], vec!["d@1.0.0".to_string()]);
+        assert_eq!(report.violations, vec!["d@1.0.0 has no license".to_string()]);
+    }
+
+    #[test]
+    fn test_allow_and_deny_lists() {
+        let packages = [
+            licensed("permissive", Some("MIT OR GPL-3.0")),
+            licensed("copyleft", Some("GPL-3.0")),
+            licensed("unknown", Some("BSD-3-Clause")),
+            licensed("invalid", Some("my license")),
+        ];
+
+        let report = check_licenses(&packages, &policy(&["MIT", "Apache-2.0"], &["GPL-3.0"]));
+
+        assert_eq!(report.violations.len(), 3);
+        assert!(report.violations[0].starts_with("copyleft@1.0.0 has license GPL-3.0 which is not allowed"));
+        assert!(report.violations[1].starts_with("unknown@1.0.0"));
+        assert!(report.violations[2].contains("not a valid SPDX expression"));
+    }
+
+    #[test]
+    fn test_deny_list_only() {
+        let packages = [licensed("a", Some("MIT")), licensed("b", Some("GPL-3.0 AND MIT"))];
+
+        let report = check_licenses(&packages, &policy(&[], &["GPL-3.0"]));
+
+        assert_eq!(report.violations.len(), 1);
+        assert!(report.violations[0].starts_with("b@1.0.0"));
+    }
+
+    #[test]
+    fn test_git_and_unresolved_dependencies_are_violations() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp_dir.path().join(MANIFEST_FILE_NAME),
+            "[package]\nname = \"my_contract\"\n\n[dependencies]\naztec = { git = \"https://github.com/AztecProtocol/aztec-packages\" }\nlocal = { path = \"../missing\" }\n",
+        ).unwrap();
+        let project = crate::workspace::find_project(temp_dir.path(), &crate::workspace::PackageSelection::default()).unwrap();
+
+        let report = licenses(&project).unwrap();
+
+        assert_eq!(report.violations.len(), 2);
+        assert!(report.violations[0].starts_with("Git dependency aztec = { git = \"https://github.com/AztecProtocol/aztec-packages\" }"));
+        assert!(report.violations[1].starts_with("Unresolved dependency local = { path = \"../missing\" }"));
+    }
+}
@@ -0,0 +1,226 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::config::MANIFEST_FILE_NAME;
+use crate::filesystem::prepare_cache_dir;
+use crate::manifest::{read_manifest, Dependency};
+use crate::ops::add::{add_dep_to_manifest, store_package};
+use crate::ops::options::ResolveOptions;
+use crate::workspace::Project;
+
+const AZTEC_PACKAGES_GIT_URL: &str = "https://github.com/AztecProtocol/aztec-packages";
+
+/// Maps a directory of a git repository to a registry package
+#[derive(Debug, Clone, Deserialize)]
+pub struct MigrationMapping {
+    /// Repository URL, the mapping applies to any repository if not set
+    pub git: Option<String>,
+    pub directory: String,
+    pub package: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MappingFile {
+    #[serde(default)]
+    mapping: Vec<MigrationMapping>,
+}
+
+#[derive(Debug)]
+pub struct MigratedDependency {
+    pub name: String,
+    pub package: String,
+    pub version: String,
+}
+
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    pub migrated: Vec<MigratedDependency>,
+    /// Git dependencies left untouched with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Mapping of Aztec git dependencies to packages published in noir-libs registry
+pub fn builtin_mappings() -> Vec<MigrationMapping> {
+    [
+        ("noir-projects/aztec-nr/aztec", "aztec"),
+        ("noir-projects/aztec-nr/address-note", "address_note"),
+        ("noir-projects/aztec-nr/authwit", "authwit"),
+        ("noir-projects/aztec-nr/compressed-string", "compressed_string"),
+        ("noir-projects/aztec-nr/easy-private-state", "easy_private_state"),
+        ("noir-projects/aztec-nr/uint-note", "uint_note"),
+        ("noir-projects/aztec-nr/value-note", "value_note"),
+        ("noir-projects/noir-protocol-circuits/crates/types", "protocol_types"),
+    ]
+    .into_iter()
+    .map(|(directory, package)| MigrationMapping {
+        git: Some(AZTEC_PACKAGES_GIT_URL.to_string()),
+        directory: directory.to_string(),
+        package: package.to_string(),
+    })
+    .collect()
+}
+
+/// Reads additional mappings from a TOML file. Example:
+///
+/// [[mapping]]
+/// git = "https://github.com/my-org/my-repo"
+/// directory = "libs/my_lib"
+/// package = "my_lib"
+pub fn read_mappings(path: &Path) -> Result<Vec<MigrationMapping>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Cannot read mapping file {}", path.display()))?;
+    let file: MappingFile = toml::from_str(&content).with_context(|| format!("Mapping file {} is invalid", path.display()))?;
+    Ok(file.mapping)
+}
+
+/// Replaces git dependencies of the selected packages with registry packages.
+///
+/// Each git dependency is mapped by its repository and directory to a registry package,
+/// the tag is used as the package version. Mapped packages are downloaded to the cache
+/// and the dependency is rewritten to a path dependency. Other dependencies and
+/// formatting of the manifest are preserved.
+///
+/// # Arguments
+///
+/// * `project` - The selected packages.
+/// * `mapping_file` - Optional file with mappings which take precedence over the built-in ones.
+/// * `options` - Resolve options.
+pub fn migrate(project: &Project, mapping_file: Option<&Path>, options: &ResolveOptions) -> Result<MigrationReport> {
+    let mut mappings = match mapping_file {
+        Some(path) => read_mappings(path)?,
+        None => Vec::new(),
+    };
+    mappings.extend(builtin_mappings());
+
+    let mut report = MigrationReport::default();
+    let mut planned = Vec::new();
+    for package_dir in &project.packages {
+        let manifest = read_manifest(package_dir)?;
+        let mut git_dependencies: Vec<_> = manifest.dependencies.iter()
+            .filter_map(|(name, dependency)| match dependency {
+                Dependency::Git { git, tag, directory } => Some((name, git, tag, directory)),
+                Dependency::Path { .. } => None,
+            })
+            .collect();
+        git_dependencies.sort_by_key(|(name, ..)| name.as_str());
+
+        for (name, git, tag, directory) in git_dependencies {
+            let Some(mapping) = find_mapping(&mappings, git, directory.as_deref().unwrap_or("")) else {
+                report.skipped.push((name.clone(), format!("no registry package is known for {} {}", git, directory.as_deref().unwrap_or(""))));
+                continue;
+            };
+            let Some(version) = tag.as_deref().and_then(version_from_tag) else {
+                report.skipped.push((name.clone(), format!("tag {} is not a version", tag.as_deref().unwrap_or("<none>"))));
+                continue;
+            };
+            planned.push((package_dir.join(MANIFEST_FILE_NAME), MigratedDependency { name: name.clone(), package: mapping.package.clone(), version }));
+        }
+    }
+
+    if options.locked && !planned.is_empty() {
+        bail!("Migrating dependencies would modify {}, but --locked or --frozen was passed", &MANIFEST_FILE_NAME);
+    }
+
+    let cache_root = prepare_cache_dir(&project.root);
+    for (manifest_path, dependency) in planned {
+        store_package(cache_root.clone(), &dependency.package, &dependency.version, false, options)
+            .map_err(|e| anyhow::anyhow!("Failed to download {}@{}: {}", dependency.package, dependency.version, e))?;
+        let manifest_dir = manifest_path.parent().expect("Failed to get manifest parent directory").to_path_buf();
+        add_dep_to_manifest(&manifest_path, manifest_dir, cache_root.clone(), &dependency.name, &dependency.package, &dependency.version);
+        report.migrated.push(dependency);
+    }
+    Ok(report)
+}
+
+fn find_mapping<'a>(mappings: &'a [MigrationMapping], git: &str, directory: &str) -> Option<&'a MigrationMapping> {
+    mappings.iter().find(|mapping| {
+        normalize_directory(&mapping.directory) == normalize_directory(directory)
+            && mapping.git.as_deref().is_none_or(|url| normalize_git_url(url) == normalize_git_url(git))
+    })
+}
+
+fn normalize_git_url(url: &str) -> String {
+    url.trim_end_matches('/').trim_end_matches(".git").to_lowercase()
+}
+
+fn normalize_directory(directory: &str) -> &str {
+    directory.trim_start_matches("./").trim_matches('/')
+}
+
+/// Converts a git tag to a package version
+/// Example: v0.67.0 -> 0.67.0, aztec-packages-v0.67.0 -> 0.67.0
+fn version_from_tag(tag: &str) -> Option<String> {
+    let version = tag.trim_start_matches(|c: char| !c.is_ascii_digit());
+    semver::Version::parse(version).ok().map(|version| version.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEPENDENCIES_FOLDER_NAME;
+    use crate::workspace::{find_project, PackageSelection};
+    use std::fs;
+    use tempfile::tempdir;
+
+    const OFFLINE: ResolveOptions = ResolveOptions { offline: true, locked: false };
+
+    #[test]
+    fn test_version_from_tag() {
+        assert_eq!(version_from_tag("v0.67.0"), Some("0.67.0".to_string()));
+        assert_eq!(version_from_tag("aztec-packages-v0.67.0"), Some("0.67.0".to_string()));
+        assert_eq!(version_from_tag("1.2.3"), Some("1.2.3".to_string()));
+        assert_eq!(version_from_tag("v1.0.0-dev"), Some("1.0.0-dev".to_string()));
+        assert_eq!(version_from_tag("master"), None);
+    }
+
+    #[test]
+    fn test_migrate() {
+        let temp_dir = tempdir().unwrap();
+        let project_dir = temp_dir.path();
+        let cache_root = project_dir.join(DEPENDENCIES_FOLDER_NAME);
+        for (package, version) in [("aztec", "0.67.0"), ("value_note", "0.67.0"), ("my_lib", "1.0.0")] {
+            let dir = cache_root.join(package).join(version);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join(MANIFEST_FILE_NAME), "[dependencies]\n").unwrap();
+        }
+        fs::write(project_dir.join(MANIFEST_FILE_NAME), indoc::indoc! {r#"
+            [package]
+            name = "my_contract"
+            type = "contract"
+
+            # Aztec dependencies
+            [dependencies]
+            aztec = { git = "https://github.com/AztecProtocol/aztec-packages/", tag = "aztec-packages-v0.67.0", directory = "noir-projects/aztec-nr/aztec" }
+            value_note = { git = "https://github.com/AztecProtocol/aztec-packages", tag = "v0.67.0", directory = "noir-projects/aztec-nr/value-note" }
+            my_lib = { git = "https://github.com/my-org/my-repo", tag = "v1.0.0", directory = "libs/my_lib" } # my library
+            unknown = { git = "https://github.com/my-org/unknown", tag = "v1.0.0" }
+        "#}).unwrap();
+        let mapping_file = project_dir.join("mapping.toml");
+        fs::write(&mapping_file, "[[mapping]]\ngit = \"https://github.com/my-org/my-repo\"\ndirectory = \"libs/my_lib\"\npackage = \"my_lib\"\n").unwrap();
+
+        let report = migrate(&find_project(project_dir, &PackageSelection::default()).unwrap(), Some(&mapping_file), &OFFLINE).unwrap();
+
+        assert_eq!(report.migrated.len(), 3);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].0, "unknown");
+        let content = fs::read_to_string(project_dir.join(MANIFEST_FILE_NAME)).unwrap();
+        assert!(content.contains("# Aztec dependencies"));
+        assert!(content.contains("aztec = { path = \".noir-libs-deps/aztec/0.67.0\" }"));
+        assert!(content.contains("value_note = { path = \".noir-libs-deps/value_note/0.67.0\" }"));
+        assert!(content.contains("my_lib = { path = \".noir-libs-deps/my_lib/1.0.0\" }"));
+        assert!(content.contains("unknown = { git = \"https://github.com/my-org/unknown\", tag = \"v1.0.0\" }"));
+    }
+
+    #[test]
+    fn test_migrate_locked() {
+        let temp_dir = tempdir().unwrap();
+        let manifest = "[package]\nname = \"my_contract\"\n\n[dependencies]\naztec = { git = \"https://github.com/AztecProtocol/aztec-packages\", tag = \"v0.67.0\", directory = \"noir-projects/aztec-nr/aztec\" }\n";
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), manifest).unwrap();
+
+        let result = migrate(&find_project(temp_dir.path(), &PackageSelection::default()).unwrap(), None, &ResolveOptions { offline: true, locked: true });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(temp_dir.path().join(MANIFEST_FILE_NAME)).unwrap(), manifest);
+    }
+}
@@ -4,3 +4,10 @@ pub mod package;
 pub mod publish;
 pub mod yank;
 pub mod fetch;
+pub mod migrate;
+pub mod cache;
+pub mod options;
+pub mod audit;
+pub mod sbom;
+pub mod licenses;
+pub mod vendor;
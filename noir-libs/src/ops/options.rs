@@ -0,0 +1,18 @@
+/// Options controlling how commands resolve and download packages
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResolveOptions {
+    /// Use only packages already available in the dependencies folder, never contact the registry
+    pub offline: bool,
+    /// Fail instead of modifying the project manifest
+    pub locked: bool,
+}
+
+impl ResolveOptions {
+    /// Creates options from the global command line flags. `frozen` implies both `offline` and `locked`.
+    pub fn new(offline: bool, locked: bool, frozen: bool) -> Self {
+        ResolveOptions {
+            offline: offline || frozen,
+            locked: locked || frozen,
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+const SOURCE_FOLDER_NAME: &str = "src";
+const LIBRARY_ENTRY_POINT: &str = "lib.nr";
+const BINARY_ENTRY_POINT: &str = "main.nr";
+const NOIR_FILE_EXTENSION: &str = "nr";
+
+// `mod foo;` and `pub mod foo;` declarations, inline modules `mod foo { ... }` have no file
+static MOD_DECLARATION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+([A-Za-z_][A-Za-z0-9_]*)\s*;").unwrap()
+});
+static BLOCK_COMMENT: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)/\*.*?\*/").unwrap());
+static LINE_COMMENT: Lazy<Regex> = Lazy::new(|| Regex::new(r"//[^\n]*").unwrap());
+
+/// Checks that the package folder has the layout of a Noir library.
+///
+/// `src/lib.nr` must exist and `src/main.nr` must not. Module declarations are followed from
+/// `src/lib.nr`, every declared module must have a file and all sources must be UTF-8.
+///
+/// # Returns
+///
+/// Warnings about `.nr` files which are never compiled and non-Noir files in the `src` folder.
+///
+/// # Errors
+///
+/// Returns an error listing all problems which prevent the library from compiling.
+pub fn check_noir_layout(package_dir: &Path) -> Result<Vec<String>> {
+    let src_dir = package_dir.join(SOURCE_FOLDER_NAME);
+    let entry_point = src_dir.join(LIBRARY_ENTRY_POINT);
+    let mut problems = Vec::new();
+    let mut warnings = Vec::new();
+
+    if src_dir.join(BINARY_ENTRY_POINT).is_file() {
+        problems.push(format!("{}/{} is a binary entry point, libraries must not contain it", SOURCE_FOLDER_NAME, BINARY_ENTRY_POINT));
+    }
+    if !entry_point.is_file() {
+        problems.push(format!("{}/{} is missing, it is the entry point of a library", SOURCE_FOLDER_NAME, LIBRARY_ENTRY_POINT));
+        bail!("Package is not a valid Noir library:\n- {}", problems.join("\n- "));
+    }
+
+    // follow module declarations from the entry point
+    let mut compiled = BTreeSet::new();
+    let mut to_visit = vec![(entry_point.clone(), src_dir.clone())];
+    while let Some((file, modules_dir)) = to_visit.pop() {
+        if !compiled.insert(file.clone()) {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            problems.push(format!("{} is not a UTF-8 text file", display(package_dir, &file)));
+            continue;
+        };
+        for module in declared_modules(&content) {
+            let module_file = modules_dir.join(format!("{}.{}", module, NOIR_FILE_EXTENSION));
+            let module_dir_file = modules_dir.join(&module).join(format!("mod.{}", NOIR_FILE_EXTENSION));
+            if module_file.is_file() {
+                to_visit.push((module_file, modules_dir.join(&module)));
+            } else if module_dir_file.is_file() {
+                to_visit.push((module_dir_file, modules_dir.join(&module)));
+            } else {
+                problems.push(format!("module {} declared in {} has no file, expected {}", module, display(package_dir, &file), display(package_dir, &module_file)));
+            }
+        }
+    }
+
+    for entry in walkdir::WalkDir::new(&src_dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some(NOIR_FILE_EXTENSION) {
+            warnings.push(format!("{} is not a Noir source file", display(package_dir, path)));
+        } else if !compiled.contains(path) && path != src_dir.join(BINARY_ENTRY_POINT) {
+            warnings.push(format!("{} is not declared as a module and will never be compiled", display(package_dir, path)));
+        }
+    }
+
+    if !problems.is_empty() {
+        bail!("Package is not a valid Noir library:\n- {}", problems.join("\n- "));
+    }
+    Ok(warnings)
+}
+
+fn declared_modules(content: &str) -> Vec<String> {
+    let without_comments = BLOCK_COMMENT.replace_all(content, "");
+    let without_comments = LINE_COMMENT.replace_all(&without_comments, "");
+    MOD_DECLARATION
+        .captures_iter(&without_comments)
+        .map(|captures| captures[1].to_string())
+        .collect()
+}
+
+fn display(package_dir: &Path, path: &Path) -> String {
+    path.strip_prefix(package_dir).map(PathBuf::from).unwrap_or_else(|_| path.to_path_buf()).display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write_files(root: &Path, files: &[(&str, &str)]) {
+        for (file, content) in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_declared_modules() {
+        let content = "mod a;\npub mod b;\npub(crate) mod c;\n// mod commented;\n/* mod block;\n*/\nmod inline {\n}\nfn main() {}";
+        assert_eq!(declared_modules(content), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_check_noir_layout_valid() {
+        let temp_dir = tempdir().unwrap();
+        write_files(temp_dir.path(), &[
+            ("src/lib.nr", "mod notes;\nmod utils;\n"),
+            ("src/notes.nr", "mod value;\n"),
+            ("src/notes/value.nr", ""),
+            ("src/utils/mod.nr", ""),
+            ("README.md", ""),
+        ]);
+
+        assert!(check_noir_layout(temp_dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_check_noir_layout_warnings() {
+        let temp_dir = tempdir().unwrap();
+        write_files(temp_dir.path(), &[
+            ("src/lib.nr", "mod utils;\n"),
+            ("src/utils.nr", ""),
+            ("src/old.nr", ""),
+            ("src/notes.txt", ""),
+        ]);
+
+        let warnings = check_noir_layout(temp_dir.path()).unwrap();
+
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("notes.txt is not a Noir source file"));
+        assert!(warnings[1].contains("old.nr is not declared as a module"));
+    }
+
+    #[test]
+    fn test_check_noir_layout_errors() {
+        let temp_dir = tempdir().unwrap();
+        write_files(temp_dir.path(), &[
+            ("src/lib.nr", "mod missing;\nmod binary;\n"),
+            ("src/main.nr", "fn main() {}"),
+        ]);
+        fs::write(temp_dir.path().join("src/binary.nr"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let error = check_noir_layout(temp_dir.path()).unwrap_err().to_string();
+
+        assert!(error.contains("main.nr is a binary entry point"));
+        assert!(error.contains("module missing declared in src/lib.nr has no file"));
+        assert!(error.contains("binary.nr is not a UTF-8 text file"));
+    }
+
+    #[test]
+    fn test_check_noir_layout_missing_entry_point() {
+        let temp_dir = tempdir().unwrap();
+        write_files(temp_dir.path(), &[("src/lib.rs", "")]);
+
+        let error = check_noir_layout(temp_dir.path()).unwrap_err().to_string();
+
+        assert!(error.contains("src/lib.nr is missing"));
+    }
+}
@@ -1,2 +1,5 @@
+pub mod layout;
 pub mod package;
-mod name_validator;
\ No newline at end of file
+pub mod secrets;
+pub mod verify;
+mod validator;
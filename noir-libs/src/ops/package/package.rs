@@ -1,48 +1,321 @@
-use crate::config::{DEPENDENCIES_FOLDER_NAME, MANIFEST_FILE_NAME};
-use crate::filesystem::{copy_all, new_dir_replace_if_exists};
-use crate::manifest::{read_manifest, Manifest, PackageType};
+use crate::config::{DEPENDENCIES_FOLDER_NAME, MANIFEST_FILE_NAME, PACKAGE_IGNORE_FILE_NAME, REGISTRY_URL};
+use crate::filesystem::{copy_all, is_file_included, list_files, new_dir_replace_if_exists};
+use crate::manifest::{get_registry_package_from_path, read_manifest, set_package_dep, Dependency, Manifest, PackageType};
+use crate::git::repository_status;
+use crate::config::METADATA_FILE_NAME;
+use crate::metadata::{metadata_content, write_metadata, DependencyMetadata, PackageMetadata, VcsMetadata};
+use crate::signing::sign_package;
 use crate::tar::create_tar_gz;
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use ed25519_dalek::SigningKey;
 use indoc::formatdoc;
-use std::path::PathBuf;
-use crate::ops::package::name_validator::validate_name_is_not_empty;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use crate::ops::options::ResolveOptions;
+use crate::ops::package::layout::check_noir_layout;
+use crate::ops::package::secrets::{scan_files_for_sensitive_data, scan_for_sensitive_files, SensitiveFinding};
+use crate::ops::package::validator::{validate_manifest, validate_name_is_not_empty};
+use crate::ops::package::verify::{find_nargo, verify_package_builds};
 
+#[derive(Debug, Clone)]
 pub struct PackagedTarball {
     pub tarball_path: String,
     pub name: String,
     pub version: String,
 }
 
-pub fn package(manifest_folder: &PathBuf, dst_folder: &PathBuf) -> Result<PackagedTarball> {
-    let manifest: Manifest = read_manifest(&manifest_folder)?;
-    verify_package_type_is_lib(&manifest)?;
-    let version = verify_and_get_version(&manifest)?;
-    let package_name = verify_and_get_package_name(&manifest)?;
+#[derive(Debug, Clone, Default)]
+pub struct PackageOptions {
+    /// Path dependencies on other workspace members, replaced in the packaged manifest
+    /// by registry references. Maps dependency name to the (package name, version) of the member.
+    pub internal_dependencies: HashMap<String, (String, String)>,
+    /// Verify that the packaged tarball builds in isolation with `nargo check`
+    pub verify: bool,
+    /// Resolve options used when fetching dependencies during verification
+    pub resolve: ResolveOptions,
+    /// Package files which likely contain secrets, only warn about them
+    pub allow_sensitive: bool,
+    /// Package files with uncommitted changes
+    pub allow_dirty: bool,
+    /// Sign the package with this key, the signature is stored in the tarball
+    pub signing_key: Option<SigningKey>,
+    /// Tarballs of other workspace members packaged earlier in the same run, used during
+    /// verification instead of the registry since they may not be published yet
+    pub local_packages: Vec<PackagedTarball>,
+}
+
+/// A file which would be packaged, the path is relative to the package root
+pub struct PackagedFile {
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+/// A package validated for packaging
+struct PackageToStage {
+    name: String,
+    version: String,
+    manifest: Manifest,
+    registry_dependencies: Vec<RegistryDependency>,
+}
+
+pub fn package(manifest_folder: &PathBuf, dst_folder: &Path, options: &PackageOptions) -> Result<PackagedTarball> {
+    let package = validate_package(manifest_folder, options)?;
+    let package_name = package.name.clone();
+    let version = package.version.clone();
 
     let temp_folder_name = format!("{}_{}", &package_name, &version);
     let temp_folder_path = &dst_folder.join(&temp_folder_name);
 
     let data_temp_folder_path = &dst_folder.join(&temp_folder_name).join(&temp_folder_name);
-    new_dir_replace_if_exists(&temp_folder_path)?;
+    new_dir_replace_if_exists(temp_folder_path)?;
 
-    copy_all(
-        &manifest_folder,
-        &data_temp_folder_path,
-        &["target", ".cargo", ".vscode", &temp_folder_name, &DEPENDENCIES_FOLDER_NAME],
-        &[".env"],
-    )?;
+    stage_package(manifest_folder, package, data_temp_folder_path, options)?;
 
     let tarball_path = &temp_folder_path.join(format!("{}_{}.tar.gz", &package_name, &version));
-    create_tar_gz(&data_temp_folder_path, &tarball_path)?;
+    create_tar_gz(data_temp_folder_path, tarball_path)?;
+    if options.verify {
+        println!("Verifying {} {}", &package_name, &version);
+        verify_package_builds(tarball_path, &find_nargo()?, &options.resolve, &options.local_packages)?;
+    }
 
     Ok(PackagedTarball {
         tarball_path: tarball_path.to_str().unwrap().to_string(),
-        name: package_name.clone(),
-        version: version.clone(),
+        name: package_name,
+        version,
     })
 }
 
-fn verify_package_type_is_lib(manifest: &Manifest) -> Result<()> {
+/// Lists the files which would be packaged, sorted by path.
+///
+/// Files are selected with the same rules as for packaging, the sizes of the rewritten manifest
+/// and of the metadata file are computed in memory, so the listed files match the tarball exactly.
+/// Nothing is written to disk.
+pub fn list_package_files(manifest_folder: &PathBuf, options: &PackageOptions) -> Result<Vec<PackagedFile>> {
+    let package = validate_package(manifest_folder, options)?;
+    let rules = PackagingRules::new(&package);
+    let paths: Vec<PathBuf> = list_files(manifest_folder, &rules.ignore_folders(), IGNORED_FILES, &rules.include, &rules.exclude)?
+        .into_iter()
+        // replaced by the generated metadata file
+        .filter(|path| path != Path::new(METADATA_FILE_NAME))
+        .collect();
+    report_sensitive_files(scan_files_for_sensitive_data(manifest_folder, &paths)?, options.allow_sensitive)?;
+    // files are reviewed before committing, uncommitted changes are expected
+    let vcs = check_repository_status(manifest_folder, &rules, true)?;
+    let manifest = packaged_manifest(manifest_folder, &package.registry_dependencies)?;
+    let metadata = metadata_content(&packaged_metadata(package, vcs))?;
+
+    let mut files = Vec::new();
+    for path in paths {
+        let size = if path == Path::new(MANIFEST_FILE_NAME) {
+            manifest.len() as u64
+        } else {
+            std::fs::metadata(manifest_folder.join(&path))?.len()
+        };
+        files.push(PackagedFile { path, size });
+    }
+    files.push(PackagedFile { path: PathBuf::from(METADATA_FILE_NAME), size: metadata.len() as u64 });
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn validate_package(manifest_folder: &PathBuf, options: &PackageOptions) -> Result<PackageToStage> {
+    let manifest: Manifest = read_manifest(manifest_folder)?;
+    // reports every problem, so the checks below pass
+    validate_manifest(&manifest)?;
+    let version = verify_and_get_version(&manifest)?;
+    let name = verify_and_get_package_name(&manifest)?.clone();
+    let registry_dependencies = resolve_registry_dependencies(&manifest, &options.internal_dependencies)?;
+    Ok(PackageToStage { name, version, manifest, registry_dependencies })
+}
+
+/// Rules selecting the packaged files of a package folder
+struct PackagingRules {
+    ignore_folders: Vec<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+const IGNORED_FILES: &[&str] = &[".env"];
+
+impl PackagingRules {
+    fn new(package: &PackageToStage) -> Self {
+        let (mut include, exclude) = match package.manifest.package.metadata.as_ref().and_then(|metadata| metadata.noir_libs.as_ref()) {
+            Some(packaging_config) => (packaging_config.include.clone(), packaging_config.exclude.clone()),
+            None => (Vec::new(), Vec::new()),
+        };
+        if !include.is_empty() {
+            // the manifest is always packaged
+            include.push(format!("/{}", MANIFEST_FILE_NAME));
+        }
+        let output_folder_name = format!("{}_{}", &package.name, &package.version);
+        let ignore_folders = ["target", ".cargo", ".vscode", &output_folder_name, DEPENDENCIES_FOLDER_NAME].map(str::to_string).to_vec();
+        PackagingRules { ignore_folders, include, exclude }
+    }
+
+    fn ignore_folders(&self) -> Vec<&str> {
+        self.ignore_folders.iter().map(String::as_str).collect()
+    }
+
+    /// Whether a file, which may not exist anymore, is packaged
+    fn is_packaged(&self, manifest_folder: &Path, relative_path: &Path) -> Result<bool> {
+        Ok(is_file_included(manifest_folder, relative_path, &self.ignore_folders(), IGNORED_FILES, &self.include, &self.exclude)?)
+    }
+}
+
+/// Copies files of the package to the staging folder, normalizes the manifest, writes the metadata file
+/// and signs the package if a signing key is set
+fn stage_package(manifest_folder: &Path, package: PackageToStage, staging_folder: &Path, options: &PackageOptions) -> Result<()> {
+    let rules = PackagingRules::new(&package);
+    copy_all(manifest_folder, staging_folder, &rules.ignore_folders(), IGNORED_FILES, &rules.include, &rules.exclude)?;
+    // checked on the staged files, so it reflects the packaged sources
+    for warning in check_noir_layout(staging_folder)? {
+        println!("{}", format!("Warning: {}", warning).yellow());
+    }
+    report_sensitive_files(scan_for_sensitive_files(staging_folder)?, options.allow_sensitive)?;
+    let vcs = check_repository_status(manifest_folder, &rules, options.allow_dirty)?;
+    let manifest_path = staging_folder.join(MANIFEST_FILE_NAME);
+    let manifest = packaged_manifest(manifest_folder, &package.registry_dependencies)?;
+    std::fs::write(&manifest_path, manifest).with_context(|| format!("Cannot write {}", manifest_path.display()))?;
+    write_metadata(staging_folder, &packaged_metadata(package, vcs))?;
+    // signed last, so the signature covers all packaged files
+    if let Some(signing_key) = &options.signing_key {
+        sign_package(staging_folder, signing_key)?;
+    }
+    Ok(())
+}
+
+/// Stops packaging when staged files have uncommitted changes, unless dirty files are allowed.
+///
+/// # Returns
+///
+/// The commit the package is packaged from, `None` outside a git repository.
+fn check_repository_status(manifest_folder: &Path, rules: &PackagingRules, allow_dirty: bool) -> Result<Option<VcsMetadata>> {
+    let status = match repository_status(manifest_folder) {
+        Ok(Some(status)) => status,
+        Ok(None) => return Ok(None),
+        Err(e) if allow_dirty => {
+            println!("{}", format!("Warning: unable to read git status: {}", e).yellow());
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+    // only changes of packaged files matter, e.g. untracked build outputs or deleted excluded files are not packaged
+    let mut dirty_files = Vec::new();
+    for path in status.changed_files.iter().chain(&status.deleted_files) {
+        if rules.is_packaged(manifest_folder, Path::new(path))? {
+            dirty_files.push(path);
+        }
+    }
+    dirty_files.sort();
+    if !dirty_files.is_empty() && !allow_dirty {
+        let files = dirty_files.iter().map(|path| format!("- {}", path)).collect::<Vec<_>>().join("\n");
+        bail!(formatdoc! {
+            "Files about to be packaged have uncommitted changes:
+             {}
+
+             Commit the changes so the package can be traced back to a commit, or pass --allow-dirty.", files });
+    }
+    Ok(Some(VcsMetadata {
+        commit: status.commit,
+        path_in_repository: status.path_in_repository,
+        dirty: !dirty_files.is_empty(),
+    }))
+}
+
+fn packaged_metadata(package: PackageToStage, vcs: Option<VcsMetadata>) -> PackageMetadata {
+    PackageMetadata {
+        name: package.name,
+        version: package.version,
+        registry: REGISTRY_URL.to_string(),
+        dependencies: package.registry_dependencies.into_iter()
+            .map(|dependency| (dependency.name, DependencyMetadata { package: dependency.package_name, version: dependency.version }))
+            .collect(),
+        vcs,
+    }
+}
+
+/// Stops packaging when packaged files likely contain secrets, unless they are explicitly allowed
+fn report_sensitive_files(findings: Vec<SensitiveFinding>, allow_sensitive: bool) -> Result<()> {
+    if findings.is_empty() {
+        return Ok(());
+    }
+    let report = findings.iter().map(|finding| format!("- {}", finding)).collect::<Vec<_>>().join("\n");
+    if allow_sensitive {
+        println!("{}", format!("Warning: packaging files which may contain sensitive data:\n{}", report).yellow());
+        return Ok(());
+    }
+    bail!(formatdoc! {
+        "Files about to be packaged may contain sensitive data:
+         {}
+
+         Published packages are public and cannot be deleted. Remove the files or secrets, exclude the files
+         in {} or with `exclude` in [package.metadata.noir-libs], or pass --allow-sensitive if they are safe to publish.",
+         report, PACKAGE_IGNORE_FILE_NAME });
+}
+
+/// A dependency of the packaged manifest which consumers resolve from the registry
+struct RegistryDependency {
+    name: String,
+    package_name: String,
+    version: String,
+}
+
+/// Checks that every dependency can be resolved by consumers of the package through the registry.
+/// Accepted are dependencies managed by noir-libs (path dependencies into the dependencies folder)
+/// and path dependencies on other workspace members which are published to the registry.
+///
+/// # Returns
+///
+/// The registry package and version of each dependency, or an error listing all dependencies
+/// which cannot be published and how to fix them.
+fn resolve_registry_dependencies(manifest: &Manifest, internal_dependencies: &HashMap<String, (String, String)>) -> Result<Vec<RegistryDependency>> {
+    let mut dependencies: Vec<_> = manifest.dependencies.iter().collect();
+    dependencies.sort_by_key(|(name, _)| name.as_str());
+
+    let mut resolved = Vec::new();
+    let mut problems = Vec::new();
+    for (name, dependency) in dependencies {
+        if let Some((package_name, version)) = internal_dependencies.get(name) {
+            resolved.push(RegistryDependency { name: name.clone(), package_name: package_name.clone(), version: version.clone() });
+            continue;
+        }
+        match dependency {
+            Dependency::Path { path } => match get_registry_package_from_path(path) {
+                Some((package_name, version)) => resolved.push(RegistryDependency { name: name.clone(), package_name, version }),
+                None => problems.push(format!(
+                    "- {} = {{ path = \"{}\" }} points to a local directory which is not available to consumers of the package. \
+                     Publish it to the registry and add it with \"noir-libs add <package>@<version>\".",
+                    name, path
+                )),
+            },
+            Dependency::Git { git, .. } => problems.push(format!(
+                "- {} = {{ git = \"{}\" }} is a git dependency which cannot be resolved through the registry. \
+                 Replace it with a registry package using \"noir-libs migrate\" or \"noir-libs add <package>@<version>\".",
+                name, git
+            )),
+        }
+    }
+    if !problems.is_empty() {
+        bail!("{} contains dependencies which cannot be published:\n{}", &MANIFEST_FILE_NAME, problems.join("\n"));
+    }
+    Ok(resolved)
+}
+
+/// Content of the packaged manifest, with all dependencies rewritten to the normalized registry form
+/// Example: my_lib = { path = "../my_lib" } -> my_lib = { path = ".noir-libs-deps/my_lib/0.1.0" }
+fn packaged_manifest(manifest_folder: &Path, dependencies: &[RegistryDependency]) -> Result<String> {
+    let manifest_path = manifest_folder.join(MANIFEST_FILE_NAME);
+    let content = std::fs::read_to_string(&manifest_path).with_context(|| format!("Cannot read {}", manifest_path.display()))?;
+    let mut doc = content.parse::<toml_edit::DocumentMut>().with_context(|| format!("{} is invalid TOML", manifest_path.display()))?;
+    for dependency in dependencies {
+        let registry_path = format!("{}/{}/{}", DEPENDENCIES_FOLDER_NAME, dependency.package_name, dependency.version);
+        set_package_dep(&mut doc, &dependency.name, &registry_path);
+    }
+    Ok(doc.to_string())
+}
+
+pub fn verify_package_type_is_lib(manifest: &Manifest) -> Result<()> {
     match &manifest.package.package_type {
         Some(package_type) => {
             if *package_type == PackageType::Library {
@@ -114,10 +387,15 @@ pub fn verify_and_get_package_name(manifest: &Manifest) -> Result<&String> {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::fs;
     use std::path::{PathBuf, MAIN_SEPARATOR};
-    use crate::ops::package::package::package;
+    use crate::ops::package::package::{list_package_files, package, PackageOptions};
+    use crate::metadata::read_metadata;
+    use ed25519_dalek::SigningKey;
+    use crate::signing::{encode_public_key, read_signature, verify_signature};
     use crate::tar::extract_tar_gz;
+    use crate::config::PACKAGE_IGNORE_FILE_NAME;
 
     const TEST_MANIFEST_FOLDER_PATH: &str = "tests/test_files/test_noir_package";
     const PACKAGING_OUTPUT_FOLDER_PATH: &str = "tests/generated";
@@ -145,7 +423,9 @@ mod tests {
         let _cleanup = TestCleanup { path: packaging_dst_folder.clone() };
 
         // when
-        let packaged_tarball = package(&manifest_folder, &packaging_dst_folder).unwrap();
+        // fixtures are in the repository and may have uncommitted changes
+        let options = PackageOptions { allow_dirty: true, ..PackageOptions::default() };
+        let packaged_tarball = package(&manifest_folder, &packaging_dst_folder, &options).unwrap();
 
         // then
         assert_eq!(packaged_tarball.name, "my_cool_package");
@@ -164,16 +444,25 @@ mod tests {
         let expected_file_path = &extract_tarball_folder.join("file_to_be_packaged.txt");
         let expected_file_path2 = &extract_tarball_folder.join("Nargo.toml");
         let expected_file_path3 = &extract_tarball_folder.join(".gitignore");
-        let expected_file_path4 = &extract_tarball_folder.join("src/lib.rs");
+        let expected_file_path4 = &extract_tarball_folder.join("src/lib.nr");
+        let expected_file_path5 = &extract_tarball_folder.join("noir-libs.json");
         assert!(expected_file_path.exists());
         assert!(expected_file_path2.exists());
         assert!(expected_file_path3.exists());
         assert!(expected_file_path4.exists());
+        assert!(expected_file_path5.exists());
 
-        // assert in my_cool_package_0.1.1 are only 4 files
+        // assert in my_cool_package_0.1.1 are only 5 files
         let extracted_tarball_files = fs::read_dir(extract_tarball_folder.as_path()).unwrap();
         let extracted_tarball_files_count = extracted_tarball_files.count();
-        assert_eq!(4, extracted_tarball_files_count);
+        assert_eq!(5, extracted_tarball_files_count);
+
+        // assert metadata describes the package and its dependencies
+        let metadata = read_metadata(&extract_tarball_folder).unwrap().unwrap();
+        assert_eq!(metadata.name, "my_cool_package");
+        assert_eq!(metadata.version, "0.1.1");
+        assert_eq!(metadata.dependencies["aztec"].package, "aztec");
+        assert_eq!(metadata.dependencies["value_note"].version, "0.67.0");
 
         // assert in src folder is only 1 file
         let extract_tarball_package_src_folder = &extract_tarball_folder.join("src");
@@ -181,4 +470,301 @@ mod tests {
         let extracted_tarball_package_src_files_count = extracted_tarball_package_src_files.count();
         assert_eq!(1, extracted_tarball_package_src_files_count);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_package_rewrites_internal_dependencies() {
+        //given
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manifest_folder = temp_dir.path().join("my_lib");
+        fs::create_dir_all(manifest_folder.join("src")).unwrap();
+        fs::write(manifest_folder.join("src/lib.nr"), "").unwrap();
+        fs::write(manifest_folder.join("Nargo.toml"), "[package]\nname = \"my_lib\"\nversion = \"0.2.0\"\ntype = \"lib\"\n\n[dependencies]\nbase = { path = \"../base\" }\n").unwrap();
+        let packaging_dst_folder = temp_dir.path().join("output");
+        let options = PackageOptions {
+            internal_dependencies: HashMap::from([("base".to_string(), ("base_lib".to_string(), "0.1.0".to_string()))]),
+            ..PackageOptions::default()
+        };
+
+        // when
+        let packaged_tarball = package(&manifest_folder, &packaging_dst_folder, &options).unwrap();
+        let extract_tarball_folder = temp_dir.path().join("extracted");
+        extract_tar_gz(PathBuf::from(packaged_tarball.tarball_path).as_path(), extract_tarball_folder.as_path()).unwrap();
+
+        // then
+        let manifest = fs::read_to_string(extract_tarball_folder.join("Nargo.toml")).unwrap();
+        assert!(manifest.contains("base = { path = \".noir-libs-deps/base_lib/0.1.0\" }"));
+        let source_manifest = fs::read_to_string(manifest_folder.join("Nargo.toml")).unwrap();
+        assert!(source_manifest.contains("base = { path = \"../base\" }"));
+    }
+
+    #[test]
+    fn test_list_package_files_matches_tarball() {
+        //given
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manifest_folder = temp_dir.path().join("my_lib");
+        fs::create_dir_all(manifest_folder.join("src")).unwrap();
+        fs::create_dir_all(manifest_folder.join("notes")).unwrap();
+        fs::write(manifest_folder.join("src/lib.nr"), "pub fn f() {}").unwrap();
+        fs::write(manifest_folder.join("notes/draft.md"), "draft").unwrap();
+        fs::write(manifest_folder.join(PACKAGE_IGNORE_FILE_NAME), "notes/\n").unwrap();
+        fs::write(manifest_folder.join("Nargo.toml"), "[package]\nname = \"my_lib\"\nversion = \"0.2.0\"\ntype = \"lib\"\n\n[dependencies]\nbase = { path = \"../base\" }\n").unwrap();
+        let options = PackageOptions {
+            internal_dependencies: HashMap::from([("base".to_string(), ("base_lib".to_string(), "0.1.0".to_string()))]),
+            ..PackageOptions::default()
+        };
+
+        // when
+        let files = list_package_files(&manifest_folder, &options).unwrap();
+        let listed_entries: Vec<_> = fs::read_dir(&manifest_folder).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        let packaged_tarball = package(&manifest_folder, &temp_dir.path().join("output"), &options).unwrap();
+        let extract_tarball_folder = temp_dir.path().join("extracted");
+        extract_tar_gz(PathBuf::from(packaged_tarball.tarball_path).as_path(), extract_tarball_folder.as_path()).unwrap();
+
+        // then
+        assert_eq!(listed_entries.len(), 4);
+        let packaged: Vec<(PathBuf, u64)> = walkdir::WalkDir::new(&extract_tarball_folder).sort_by_file_name().into_iter()
+            .map(|entry| entry.unwrap())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| (entry.path().strip_prefix(&extract_tarball_folder).unwrap().to_path_buf(), entry.metadata().unwrap().len()))
+            .collect();
+        let listed: Vec<(PathBuf, u64)> = files.into_iter().map(|file| (file.path, file.size)).collect();
+        assert_eq!(listed, packaged);
+    }
+
+    #[test]
+    fn test_package_normalizes_registry_dependencies() {
+        //given
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manifest_folder = temp_dir.path().join("crates/my_lib");
+        fs::create_dir_all(manifest_folder.join("src")).unwrap();
+        fs::write(manifest_folder.join("src/lib.nr"), "").unwrap();
+        fs::write(manifest_folder.join("Nargo.toml"), "[package]\nname = \"my_lib\"\nversion = \"0.2.0\"\ntype = \"lib\"\n\n[dependencies]\naztec = { path = \"../../.noir-libs-deps/aztec/0.67.0\" }\n").unwrap();
+
+        // when
+        let packaged_tarball = package(&manifest_folder, &temp_dir.path().join("output"), &PackageOptions::default()).unwrap();
+        let extract_tarball_folder = temp_dir.path().join("extracted");
+        extract_tar_gz(PathBuf::from(packaged_tarball.tarball_path).as_path(), extract_tarball_folder.as_path()).unwrap();
+
+        // then
+        let manifest = fs::read_to_string(extract_tarball_folder.join("Nargo.toml")).unwrap();
+        assert!(manifest.contains("aztec = { path = \".noir-libs-deps/aztec/0.67.0\" }"));
+    }
+
+    #[test]
+    fn test_package_rejects_unpublishable_dependencies() {
+        //given
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manifest_folder = temp_dir.path().join("my_lib");
+        fs::create_dir_all(manifest_folder.join("src")).unwrap();
+        fs::write(manifest_folder.join("src/lib.nr"), "").unwrap();
+        fs::write(manifest_folder.join("Nargo.toml"), indoc::indoc! {r#"
+            [package]
+            name = "my_lib"
+            version = "0.2.0"
+            type = "lib"
+
+            [dependencies]
+            aztec = { git = "https://github.com/AztecProtocol/aztec-packages", tag = "v0.67.0", directory = "noir-projects/aztec-nr/aztec" }
+            sibling = { path = "../sibling" }
+            value_note = { path = ".noir-libs-deps/value_note/0.67.0" }
+        "#}).unwrap();
+        let packaging_dst_folder = temp_dir.path().join("output");
+
+        // when
+        let error = package(&manifest_folder, &packaging_dst_folder, &PackageOptions::default()).err().unwrap().to_string();
+
+        // then
+        assert!(error.contains("aztec = { git = \"https://github.com/AztecProtocol/aztec-packages\" }"));
+        assert!(error.contains("noir-libs migrate"));
+        assert!(error.contains("sibling = { path = \"../sibling\" }"));
+        assert!(!error.contains("value_note"));
+        assert!(!packaging_dst_folder.exists());
+    }
+
+    #[test]
+    fn test_package_include_exclude_rules() {
+        //given
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manifest_folder = temp_dir.path().join("my_lib");
+        for file in ["src/lib.nr", "src/test/fixtures.nr", "benches/proof.bin", "README.md"] {
+            let path = manifest_folder.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        fs::write(manifest_folder.join("Nargo.toml"), indoc::indoc! {r#"
+            [package]
+            name = "my_lib"
+            version = "0.2.0"
+            type = "lib"
+
+            [package.metadata.noir-libs]
+            include = ["src/**", "README.md"]
+            exclude = ["src/test/**"]
+        "#}).unwrap();
+
+        // when
+        let packaged_tarball = package(&manifest_folder, &temp_dir.path().join("output"), &PackageOptions::default()).unwrap();
+        let extract_tarball_folder = temp_dir.path().join("extracted");
+        extract_tar_gz(PathBuf::from(packaged_tarball.tarball_path).as_path(), extract_tarball_folder.as_path()).unwrap();
+
+        // then
+        assert!(extract_tarball_folder.join("Nargo.toml").is_file());
+        assert!(extract_tarball_folder.join("README.md").is_file());
+        assert!(extract_tarball_folder.join("src/lib.nr").is_file());
+        assert!(!extract_tarball_folder.join("src/test").exists());
+        assert!(!extract_tarball_folder.join("benches").exists());
+    }
+
+    #[test]
+    fn test_list_package_files() {
+        //given
+        let current_dir = std::env::current_dir().unwrap();
+        let manifest_folder = current_dir.join(TEST_MANIFEST_FOLDER_PATH);
+
+        // when
+        let files = list_package_files(&manifest_folder, &PackageOptions::default()).unwrap();
+
+        // then
+        let paths: Vec<String> = files.iter().map(|file| file.path.to_str().unwrap().replace(MAIN_SEPARATOR, "/")).collect();
+        assert_eq!(paths, vec![".gitignore", "Nargo.toml", "file_to_be_packaged.txt", "noir-libs.json", "src/lib.nr"]);
+        assert_eq!(files[2].size, fs::metadata(manifest_folder.join("file_to_be_packaged.txt")).unwrap().len());
+        assert!(!manifest_folder.join("target/package").exists());
+    }
+
+    #[test]
+    fn test_package_is_reproducible() {
+        //given
+        let current_dir = std::env::current_dir().unwrap();
+        let manifest_folder = current_dir.join(TEST_MANIFEST_FOLDER_PATH);
+        let temp_dir = tempfile::tempdir().unwrap();
+
+        // when
+        let options = PackageOptions { allow_dirty: true, ..PackageOptions::default() };
+        let first = package(&manifest_folder, &temp_dir.path().join("first"), &options).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let second = package(&manifest_folder, &temp_dir.path().join("second"), &options).unwrap();
+
+        // then
+        assert_eq!(fs::read(first.tarball_path).unwrap(), fs::read(second.tarball_path).unwrap());
+    }
+
+    #[test]
+    fn test_package_signs_tarball() {
+        //given
+        let current_dir = std::env::current_dir().unwrap();
+        let manifest_folder = current_dir.join(TEST_MANIFEST_FOLDER_PATH);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+
+        // when
+        let options = PackageOptions { allow_dirty: true, signing_key: Some(signing_key.clone()), ..PackageOptions::default() };
+        let packaged_tarball = package(&manifest_folder, &temp_dir.path().join("output"), &options).unwrap();
+
+        // then
+        let extract_tarball_folder = temp_dir.path().join("extracted");
+        extract_tar_gz(PathBuf::from(packaged_tarball.tarball_path).as_path(), extract_tarball_folder.as_path()).unwrap();
+        let signature = read_signature(&extract_tarball_folder).unwrap().expect("Package is not signed");
+        assert_eq!(signature.public_key, encode_public_key(&signing_key.verifying_key()));
+        assert!(verify_signature(&extract_tarball_folder, &signature).is_ok());
+    }
+
+    #[test]
+    fn test_package_rejects_sensitive_files() {
+        //given
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manifest_folder = temp_dir.path().join("my_lib");
+        fs::create_dir_all(manifest_folder.join("src")).unwrap();
+        fs::write(manifest_folder.join("src/lib.nr"), "").unwrap();
+        fs::write(manifest_folder.join(".env.production"), "DEPLOYER=1\n").unwrap();
+        fs::write(manifest_folder.join("Nargo.toml"), "[package]\nname = \"my_lib\"\nversion = \"0.2.0\"\ntype = \"lib\"\n").unwrap();
+
+        // when
+        let error = package(&manifest_folder, &temp_dir.path().join("output"), &PackageOptions::default()).err().unwrap().to_string();
+        let allowed = package(&manifest_folder, &temp_dir.path().join("output"), &PackageOptions { allow_sensitive: true, ..PackageOptions::default() });
+
+        // then
+        assert!(error.contains(".env.production: environment file"));
+        assert!(error.contains("--allow-sensitive"));
+        assert!(allowed.is_ok());
+    }
+
+    fn git(dir: &std::path::Path, args: &[&str]) {
+        let status = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com", "-c", "init.defaultBranch=main"])
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[test]
+    fn test_package_refuses_uncommitted_changes() {
+        //given
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repository = temp_dir.path().join("repository");
+        let manifest_folder = repository.join("libs/my_lib");
+        fs::create_dir_all(manifest_folder.join("src")).unwrap();
+        fs::write(manifest_folder.join("src/lib.nr"), "").unwrap();
+        fs::write(manifest_folder.join("Nargo.toml"), "[package]\nname = \"my_lib\"\nversion = \"0.2.0\"\ntype = \"lib\"\n").unwrap();
+        fs::write(repository.join(".gitignore"), "target\n").unwrap();
+        git(&repository, &["init", "-q"]);
+        git(&repository, &["add", "-A"]);
+        git(&repository, &["commit", "-q", "-m", "init"]);
+        let output = repository.join("target");
+
+        // when
+        let clean = package(&manifest_folder, &output, &PackageOptions::default()).unwrap();
+        let extract_tarball_folder = temp_dir.path().join("extracted");
+        extract_tar_gz(PathBuf::from(clean.tarball_path).as_path(), extract_tarball_folder.as_path()).unwrap();
+        fs::write(manifest_folder.join("src/lib.nr"), "pub fn changed() {}").unwrap();
+        let dirty = package(&manifest_folder, &output, &PackageOptions::default());
+        let allowed = package(&manifest_folder, &output, &PackageOptions { allow_dirty: true, ..PackageOptions::default() }).unwrap();
+
+        // then
+        let vcs = read_metadata(&extract_tarball_folder).unwrap().unwrap().vcs.unwrap();
+        assert_eq!(vcs.commit.len(), 40);
+        assert_eq!(vcs.path_in_repository, "libs/my_lib");
+        assert!(!vcs.dirty);
+
+        let error = dirty.err().unwrap().to_string();
+        assert!(error.contains("- src/lib.nr"));
+        assert!(error.contains("--allow-dirty"));
+
+        let extract_tarball_folder = temp_dir.path().join("extracted_dirty");
+        extract_tar_gz(PathBuf::from(allowed.tarball_path).as_path(), extract_tarball_folder.as_path()).unwrap();
+        assert!(read_metadata(&extract_tarball_folder).unwrap().unwrap().vcs.unwrap().dirty);
+    }
+
+    #[test]
+    fn test_package_ignores_deleted_files_which_are_not_packaged() {
+        //given
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repository = temp_dir.path().join("repository");
+        let manifest_folder = repository.join("libs/my_lib");
+        fs::create_dir_all(manifest_folder.join("src")).unwrap();
+        fs::create_dir_all(manifest_folder.join("notes")).unwrap();
+        fs::write(manifest_folder.join("src/lib.nr"), "").unwrap();
+        fs::write(manifest_folder.join("src/other.nr"), "").unwrap();
+        fs::write(manifest_folder.join("notes/draft.md"), "").unwrap();
+        fs::write(manifest_folder.join(PACKAGE_IGNORE_FILE_NAME), "notes/\n").unwrap();
+        fs::write(manifest_folder.join("Nargo.toml"), "[package]\nname = \"my_lib\"\nversion = \"0.2.0\"\ntype = \"lib\"\n").unwrap();
+        fs::write(repository.join(".gitignore"), "target\n").unwrap();
+        git(&repository, &["init", "-q"]);
+        git(&repository, &["add", "-A"]);
+        git(&repository, &["commit", "-q", "-m", "init"]);
+        let output = repository.join("target");
+
+        // when
+        fs::remove_file(manifest_folder.join("notes/draft.md")).unwrap();
+        let excluded_deleted = package(&manifest_folder, &output, &PackageOptions::default());
+        fs::remove_file(manifest_folder.join("src/other.nr")).unwrap();
+        let packaged_deleted = package(&manifest_folder, &output, &PackageOptions::default());
+
+        // then
+        assert!(excluded_deleted.is_ok(), "{:?}", excluded_deleted.err());
+        let error = packaged_deleted.err().unwrap().to_string();
+        assert!(error.contains("- src/other.nr"));
+        assert!(!error.contains("notes/draft.md"));
+    }
+}
@@ -0,0 +1,191 @@
+use anyhow::bail;
+use reqwest::Url;
+use crate::config::MANIFEST_FILE_NAME;
+use crate::manifest::{Manifest, PackageType};
+
+// Package rules of the registry, enforced by its publish endpoint (REGISTRY_URL). The registry backend is not
+// part of this repository, so these values must be updated together with its rules. They are checked locally
+// so publishing fails before the upload, the registry stays authoritative and may still reject a package.
+pub const MAX_NAME_LENGTH: usize = 64;
+pub const MAX_KEYWORDS: usize = 5;
+pub const MAX_KEYWORD_LENGTH: usize = 20;
+pub const MAX_DESCRIPTION_LENGTH: usize = 1000;
+// Names of Noir built-in crates and keywords used in dependency paths
+const RESERVED_NAMES: &[&str] = &["std", "core", "crate", "dep", "super", "self", "main", "test", "nargo", "noir_libs"];
+
+pub fn validate_name_is_not_empty(name: &str) -> anyhow::Result<()> {
+    // Must not be empty
+    if name.trim().is_empty() {
+        bail! {"package name {} in {} name is invalid. It cannot be empty.", &name, MANIFEST_FILE_NAME };
+    }
+
+    Ok(())
+}
+
+/// Validates the package fields of the manifest against the registry rules: package `type`,
+/// semantic `version`, name characters, length and reserved names, SPDX `license` expression,
+/// keywords, description length and `repository` / `documentation` URLs.
+///
+/// # Errors
+///
+/// Returns an error listing all problems found.
+pub fn validate_manifest(manifest: &Manifest) -> anyhow::Result<()> {
+    let package = &manifest.package;
+    let mut problems = Vec::new();
+
+    match &package.package_type {
+        Some(PackageType::Library) => {}
+        Some(package_type) => problems.push(format!("package type {} cannot be published, set type = \"{}\"", package_type, PackageType::Library)),
+        None => problems.push(format!("package type is not set, set type = \"{}\"", PackageType::Library)),
+    }
+    match &package.version {
+        Some(version) if semver::Version::parse(version).is_err() => {
+            problems.push(format!("version \"{}\" is not a valid semantic version. Example: version = \"0.0.1\"", version))
+        }
+        Some(_) => {}
+        None => problems.push("version is not set. Example: version = \"0.0.1\"".to_string()),
+    }
+    match &package.name {
+        Some(name) if name.trim().is_empty() => problems.push("name must not be empty".to_string()),
+        Some(name) => problems.extend(validate_name(name)),
+        None => problems.push("name is not set. Example: name = \"my_lib\"".to_string()),
+    }
+    if let Some(license) = &package.license {
+        if let Err(e) = spdx::Expression::parse(license) {
+            problems.push(format!("license \"{}\" is not a valid SPDX license expression ({}). Example: license = \"MIT OR Apache-2.0\"", license, e.reason));
+        }
+    }
+    if let Some(keywords) = &package.keywords {
+        if keywords.len() > MAX_KEYWORDS {
+            problems.push(format!("too many keywords ({}), at most {} are allowed", keywords.len(), MAX_KEYWORDS));
+        }
+        for keyword in keywords {
+            problems.extend(validate_keyword(keyword));
+        }
+    }
+    if let Some(description) = &package.description {
+        let length = description.chars().count();
+        if length > MAX_DESCRIPTION_LENGTH {
+            problems.push(format!("description is too long ({} characters), at most {} are allowed", length, MAX_DESCRIPTION_LENGTH));
+        }
+    }
+    for (field, url) in [("repository", &package.repository), ("documentation", &package.documentation)] {
+        if let Some(url) = url {
+            problems.extend(validate_url(field, url));
+        }
+    }
+
+    if !problems.is_empty() {
+        bail!("{} does not meet the registry rules:\n- {}", MANIFEST_FILE_NAME, problems.join("\n- "));
+    }
+    Ok(())
+}
+
+/// Package names start with a letter and contain only ASCII letters, digits and `_`
+fn validate_name(name: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    if name.len() > MAX_NAME_LENGTH {
+        problems.push(format!("name \"{}\" is too long ({} characters), at most {} are allowed", name, name.len(), MAX_NAME_LENGTH));
+    }
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        problems.push(format!("name \"{}\" must start with a letter", name));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        problems.push(format!("name \"{}\" may contain only ASCII letters, digits and \"_\"", name));
+    }
+    if RESERVED_NAMES.contains(&name.to_lowercase().as_str()) {
+        problems.push(format!("name \"{}\" is reserved", name));
+    }
+    problems
+}
+
+fn validate_keyword(keyword: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    if keyword.is_empty() || keyword.len() > MAX_KEYWORD_LENGTH {
+        problems.push(format!("keyword \"{}\" must have 1 to {} characters", keyword, MAX_KEYWORD_LENGTH));
+    }
+    if !keyword.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        problems.push(format!("keyword \"{}\" may contain only ASCII letters, digits, \"_\" and \"-\"", keyword));
+    }
+    problems
+}
+
+fn validate_url(field: &str, url: &str) -> Option<String> {
+    match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.host().is_some() => None,
+        Ok(_) => Some(format!("{} \"{}\" must be an http or https URL", field, url)),
+        Err(e) => Some(format!("{} \"{}\" is not a valid URL ({})", field, url, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(package: &str) -> Manifest {
+        toml::from_str(&format!("[package]\n{}", package)).unwrap()
+    }
+
+    #[test]
+    fn test_validate_manifest_valid() {
+        let manifest = manifest(indoc::indoc! {r#"
+            name = "value_note2"
+            type = "lib"
+            version = "0.1.0"
+            license = "MIT OR Apache-2.0"
+            keywords = ["aztec", "notes", "zk-proofs"]
+            description = "Value notes"
+            repository = "https://github.com/my-org/value-note"
+            documentation = "http://docs.example.com/value_note"
+        "#});
+
+        assert!(validate_manifest(&manifest).is_ok());
+    }
+
+    #[test]
+    fn test_validate_manifest_reports_all_problems() {
+        let manifest = manifest(&format!(indoc::indoc! {r#"
+            name = "std"
+            type = "contract"
+            version = "1.0"
+            license = "MIT OR Apache"
+            keywords = ["a", "b", "c", "d", "e", "keyword with spaces"]
+            description = "{}"
+            repository = "github.com/my-org/repo"
+            documentation = "ftp://docs.example.com"
+        "#}, "x".repeat(MAX_DESCRIPTION_LENGTH + 1)));
+
+        let error = validate_manifest(&manifest).unwrap_err().to_string();
+
+        assert!(error.contains("package type contract cannot be published"));
+        assert!(error.contains("version \"1.0\" is not a valid semantic version"));
+        assert!(error.contains("name \"std\" is reserved"));
+        assert!(error.contains("license \"MIT OR Apache\""));
+        assert!(error.contains("too many keywords (6)"));
+        assert!(error.contains("keyword \"keyword with spaces\""));
+        assert!(error.contains("description is too long"));
+        assert!(error.contains("repository \"github.com/my-org/repo\" is not a valid URL"));
+        assert!(error.contains("documentation \"ftp://docs.example.com\" must be an http or https URL"));
+    }
+
+    #[test]
+    fn test_validate_manifest_reports_missing_fields() {
+        let manifest = manifest("license = \"MIT OR Apache\"\n");
+
+        let error = validate_manifest(&manifest).unwrap_err().to_string();
+
+        assert!(error.contains("package type is not set"));
+        assert!(error.contains("version is not set"));
+        assert!(error.contains("name is not set"));
+        assert!(error.contains("license \"MIT OR Apache\""));
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("my_lib").is_empty());
+        assert_eq!(validate_name("1lib").len(), 1);
+        assert_eq!(validate_name("my-lib").len(), 1);
+        assert_eq!(validate_name(&"a".repeat(MAX_NAME_LENGTH + 1)).len(), 1);
+        assert_eq!(validate_name("Dep").len(), 1);
+    }
+}
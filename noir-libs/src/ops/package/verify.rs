@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::filesystem::prepare_cache_dir;
+use crate::metadata::read_package_dependencies;
+use crate::ops::add::{link_cached_dependencies, store_package};
+use crate::ops::options::ResolveOptions;
+use crate::ops::package::package::PackagedTarball;
+use crate::path::get_package_dir;
+use crate::tar::extract_tar_gz;
+
+/// Locates the system `nargo` executable.
+pub fn find_nargo() -> Result<PathBuf> {
+    which::which("nargo").context("Unable to find nargo executable required to verify the package. Please install nargo and make sure it is available in PATH or pass --no-verify.")
+}
+
+/// Verifies that a packaged tarball builds in isolation from the project it was packaged from.
+///
+/// The tarball is extracted into a temporary directory, its dependencies are fetched
+/// there and `nargo check` is run on the extracted package.
+///
+/// # Arguments
+///
+/// * `tarball_path` - The path to the packaged tarball.
+/// * `nargo` - The nargo executable.
+/// * `options` - Resolve options used when fetching dependencies.
+/// * `local_packages` - Tarballs of other workspace members packaged earlier in the same run. They are
+///   used instead of the registry, so members depending on not yet published members can be verified.
+pub fn verify_package_builds(tarball_path: &Path, nargo: &Path, options: &ResolveOptions, local_packages: &[PackagedTarball]) -> Result<()> {
+    let temp_dir = tempfile::tempdir().context("Failed to create a temporary directory for verification")?;
+    let package_dir = temp_dir.path();
+    extract_tar_gz(tarball_path, package_dir).with_context(|| format!("Failed to extract {}", tarball_path.display()))?;
+
+    fetch_dependencies(package_dir, options, local_packages).context("Failed to fetch dependencies of the packaged package")?;
+
+    let output = Command::new(nargo)
+        .arg("check")
+        .current_dir(package_dir)
+        .output()
+        .with_context(|| format!("Failed to run {}", nargo.display()))?;
+    if !output.status.success() {
+        bail!(
+            "Packaged tarball does not build, nargo check failed:\n{}{}\nFix the errors or pass --no-verify to skip verification.",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+    }
+    Ok(())
+}
+
+/// Stores all dependencies of the extracted package in its dependencies folder. Local packages are
+/// extracted from their tarballs, the other dependencies are fetched from the registry.
+fn fetch_dependencies(package_dir: &Path, options: &ResolveOptions, local_packages: &[PackagedTarball]) -> Result<()> {
+    let cache_root = prepare_cache_dir(&package_dir.to_path_buf());
+    for local_package in local_packages {
+        let local_package_dir = get_package_dir(cache_root.clone(), &local_package.name, &local_package.version);
+        extract_tar_gz(Path::new(&local_package.tarball_path), &local_package_dir)
+            .with_context(|| format!("Failed to extract {}", local_package.tarball_path))?;
+        link_cached_dependencies(&cache_root, &local_package_dir).map_err(|e| anyhow!(e))?;
+    }
+
+    let mut pending = read_package_dependencies(package_dir)?;
+    let mut visited = HashSet::new();
+    while let Some((package_name, version)) = pending.pop() {
+        if !visited.insert((package_name.clone(), version.clone())) {
+            continue;
+        }
+        if local_packages.iter().any(|local_package| local_package.name == package_name && local_package.version == version) {
+            // dependencies of local packages may still come from the registry
+            pending.extend(read_package_dependencies(&get_package_dir(cache_root.clone(), &package_name, &version))?);
+            continue;
+        }
+        store_package(cache_root.clone(), &package_name, &version, true, options)
+            .map_err(|e| anyhow!("Fetching dependency {}@{} failed: {}", package_name, version, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::tar::create_tar_gz;
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    const OFFLINE: ResolveOptions = ResolveOptions { offline: true, locked: false };
+
+    fn write_stub_nargo(dir: &Path, script: &str) -> PathBuf {
+        let path = dir.join("nargo");
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn create_tarball(dir: &Path) -> PathBuf {
+        let package_dir = dir.join("my_lib");
+        fs::create_dir_all(package_dir.join("src")).unwrap();
+        fs::write(package_dir.join("src/lib.nr"), "").unwrap();
+        fs::write(package_dir.join("Nargo.toml"), "[package]\nname = \"my_lib\"\nversion = \"0.1.0\"\ntype = \"lib\"\n").unwrap();
+        let tarball_path = dir.join("my_lib_0.1.0.tar.gz");
+        create_tar_gz(&package_dir, &tarball_path).unwrap();
+        tarball_path
+    }
+
+    #[test]
+    fn test_verify_package_builds() {
+        let temp_dir = tempdir().unwrap();
+        let tarball_path = create_tarball(temp_dir.path());
+        // the stub fails unless it runs in the extracted package
+        let nargo = write_stub_nargo(temp_dir.path(), "[ \"$1\" = check ] && [ -f Nargo.toml ] && [ -f src/lib.nr ]");
+
+        assert!(verify_package_builds(&tarball_path, &nargo, &OFFLINE, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_verify_package_builds_failure() {
+        let temp_dir = tempdir().unwrap();
+        let tarball_path = create_tarball(temp_dir.path());
+        let nargo = write_stub_nargo(temp_dir.path(), "echo \"error: cannot find module\" >&2\nexit 1");
+
+        let error = verify_package_builds(&tarball_path, &nargo, &OFFLINE, &[]).unwrap_err().to_string();
+
+        assert!(error.contains("nargo check failed"));
+        assert!(error.contains("cannot find module"));
+    }
+
+    #[test]
+    fn test_verify_member_depending_on_unpublished_member() {
+        use crate::ops::package::package::{package, PackageOptions};
+        use crate::ops::publish::get_internal_dependencies;
+        use crate::workspace::{find_project, PackageSelection};
+
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("workspace");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("Nargo.toml"), "[workspace]\nmembers = [\"base\", \"top\"]\n").unwrap();
+        for (name, dependencies) in [("base", ""), ("top", "base = { path = \"../base\" }\n")] {
+            fs::create_dir_all(root.join(name).join("src")).unwrap();
+            fs::write(root.join(name).join("src/lib.nr"), "").unwrap();
+            fs::write(
+                root.join(name).join("Nargo.toml"),
+                format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\ntype = \"lib\"\n\n[dependencies]\n{}", name, dependencies),
+            ).unwrap();
+        }
+        let project = find_project(&root, &PackageSelection { workspace: true, package: None }).unwrap();
+        let package_member = |name: &str| {
+            let dir = root.join(name);
+            let options = PackageOptions { internal_dependencies: get_internal_dependencies(&project, &dir).unwrap(), ..PackageOptions::default() };
+            package(&dir, &dir.join("target"), &options).unwrap()
+        };
+        let base = package_member("base");
+        let top = package_member("top");
+        // the stub fails unless the sibling is available in the dependencies folder
+        let nargo = write_stub_nargo(temp_dir.path(), "[ -f .noir-libs-deps/base/0.1.0/src/lib.nr ] && grep -q '.noir-libs-deps/base/0.1.0' Nargo.toml");
+
+        assert!(verify_package_builds(Path::new(&top.tarball_path), &nargo, &OFFLINE, &[]).is_err());
+        assert!(verify_package_builds(Path::new(&top.tarball_path), &nargo, &OFFLINE, &[base]).is_ok());
+    }
+}
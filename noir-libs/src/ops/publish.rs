@@ -1,13 +1,209 @@
-use crate::api::api::publish_package_api;
-use crate::config::{API_KEY_ENV_VAR_NAME, PACKAGING_OUTPUT_FOLDER_PATH, REGISTRY_HOME_URL};
-use crate::manifest::{read_manifest, Manifest};
-use crate::ops::package::package::{verify_and_get_package_name, verify_and_get_version, PackagedTarball};
+use crate::api::api::{package_version_exists_api, publish_package_api};
+use crate::cache::format_size;
+use crate::config::{API_KEY_ENV_VAR_NAME, MANIFEST_FILE_NAME, PACKAGING_OUTPUT_FOLDER_PATH, REGISTRY_HOME_URL, REGISTRY_URL};
+use crate::manifest::{read_manifest, Dependency, PackageType};
+use crate::ops::options::ResolveOptions;
+use crate::ops::package::package::{package, verify_and_get_package_name, verify_and_get_version, verify_package_type_is_lib, PackageOptions, PackagedTarball};
+use crate::workspace::Project;
 use anyhow::{bail, Result};
+use ed25519_dalek::SigningKey;
 use indoc::formatdoc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
-pub fn publish() -> Result<String> {
-    let api_key = match std::env::var(&API_KEY_ENV_VAR_NAME) {
-        Ok(api_key) => api_key,
+/// A library member of a workspace which can be published
+#[derive(Debug)]
+struct WorkspaceLibrary {
+    dir: PathBuf,
+    name: String,
+    version: String,
+    /// Path dependencies on other library members: dependency name -> (package name, version)
+    internal_dependencies: HashMap<String, (String, String)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PublishOptions {
+    /// Perform all validation and packaging steps, but do not upload anything
+    pub dry_run: bool,
+    /// Verify that each packaged tarball builds in isolation before uploading it
+    pub verify: bool,
+    /// Resolve options used when fetching dependencies during verification
+    pub resolve: ResolveOptions,
+    /// Publish files which likely contain secrets
+    pub allow_sensitive: bool,
+    /// Publish files with uncommitted changes
+    pub allow_dirty: bool,
+    /// Sign published packages with this key
+    pub signing_key: Option<SigningKey>,
+    /// Publishing all workspace members (`--workspace`), versions already in the registry are skipped.
+    /// Otherwise publishing an already published version is an error.
+    pub workspace: bool,
+}
+
+/// Packages and publishes the selected library packages in dependency order.
+///
+/// Tarballs are always packaged from current sources, so a stale tarball is never uploaded.
+/// With `--workspace` versions already available in the registry are skipped. Path dependencies on other workspace
+/// members are replaced with registry references in the packaged manifest.
+pub fn publish(project: &Project, options: &PublishOptions) -> Result<String> {
+    let api_key = if options.dry_run { None } else { Some(get_api_key()?) };
+    if let [package_dir] = project.packages.as_slice() {
+        // report the exact problem when a single package is not publishable
+        verify_package_type_is_lib(&read_manifest(package_dir)?)?;
+    }
+    let libraries = read_workspace_libraries(project)?;
+    let selected: Vec<&WorkspaceLibrary> = libraries.iter()
+        .filter(|library| project.packages.contains(&library.dir))
+        .collect();
+    if selected.is_empty() {
+        bail!("No library packages to publish. Only packages with type = \"{}\" can be published.", PackageType::Library);
+    }
+
+    let mut published = 0;
+    let mut skipped = 0;
+    let mut packaged_tarballs: Vec<PackagedTarball> = Vec::new();
+    for library in publish_order(&selected)? {
+        if package_version_exists_api(&library.name, &library.version)? {
+            check_can_skip_published(library, options.workspace)?;
+            println!("Skipping {} {}: version is already published", library.name, library.version);
+            skipped += 1;
+            continue;
+        }
+        for (package_name, version) in library.internal_dependencies.values() {
+            let will_be_published = selected.iter().any(|other| &other.name == package_name);
+            if !will_be_published && !package_version_exists_api(package_name, version)? {
+                bail!("Package {} depends on workspace member {} {} which is not published. Publish it first or use --workspace.", library.name, package_name, version);
+            }
+        }
+        let package_options = PackageOptions {
+            internal_dependencies: library.internal_dependencies.clone(),
+            verify: options.verify,
+            resolve: options.resolve,
+            allow_sensitive: options.allow_sensitive,
+            allow_dirty: options.allow_dirty,
+            signing_key: options.signing_key.clone(),
+            local_packages: packaged_tarballs.clone(),
+        };
+        let packaged_tarball = package(&library.dir, &library.dir.join(PACKAGING_OUTPUT_FOLDER_PATH), &package_options)?;
+        match &api_key {
+            Some(api_key) => println!("{}", publish_package_api(&packaged_tarball, api_key.clone())?),
+            None => println!("{}", describe_dry_run(&packaged_tarball)?),
+        }
+        packaged_tarballs.push(packaged_tarball);
+        published += 1;
+    }
+    if options.dry_run {
+        Ok(format!("Dry run: {} packages would be published, {} already published packages would be skipped. Nothing was uploaded.", published, skipped))
+    } else {
+        Ok(format!("Published {} packages, skipped {} already published packages.", published, skipped))
+    }
+}
+
+// Already published versions are skipped only when publishing the whole workspace,
+// an explicitly selected package must not report success without uploading anything
+fn check_can_skip_published(library: &WorkspaceLibrary, workspace: bool) -> Result<()> {
+    if !workspace {
+        bail!("Package {} {} is already published. Bump the version in {} to publish a new version.", library.name, library.version, MANIFEST_FILE_NAME);
+    }
+    Ok(())
+}
+
+fn describe_dry_run(packaged_tarball: &PackagedTarball) -> Result<String> {
+    let size = std::fs::metadata(&packaged_tarball.tarball_path)?.len();
+    Ok(formatdoc! {
+        "Would upload {} {} to {}
+         Tarball: {} ({})",
+        &packaged_tarball.name, &packaged_tarball.version, &REGISTRY_URL, &packaged_tarball.tarball_path, format_size(size) })
+}
+
+/// Finds path dependencies of a package on other library members of the workspace.
+///
+/// # Returns
+///
+/// Map of dependency name to the (package name, version) of the member.
+pub fn get_internal_dependencies(project: &Project, package_dir: &Path) -> Result<HashMap<String, (String, String)>> {
+    Ok(read_workspace_libraries(project)?
+        .into_iter()
+        .find(|library| library.dir == package_dir)
+        .map(|library| library.internal_dependencies)
+        .unwrap_or_default())
+}
+
+/// Orders the selected packages so that library members come after the library members they depend on.
+/// Other packages (e.g. contracts) keep their order and come last.
+pub fn package_order(project: &Project) -> Result<Vec<PathBuf>> {
+    let libraries = read_workspace_libraries(project)?;
+    let selected: Vec<&WorkspaceLibrary> = libraries.iter()
+        .filter(|library| project.packages.contains(&library.dir))
+        .collect();
+    let mut ordered: Vec<PathBuf> = publish_order(&selected)?.into_iter().map(|library| library.dir.clone()).collect();
+    for package_dir in &project.packages {
+        if !ordered.contains(package_dir) {
+            ordered.push(package_dir.clone());
+        }
+    }
+    Ok(ordered)
+}
+
+fn read_workspace_libraries(project: &Project) -> Result<Vec<WorkspaceLibrary>> {
+    let mut manifests = Vec::new();
+    for manifest_path in &project.all_manifests {
+        let dir = manifest_path.parent().expect("Failed to get manifest parent directory").to_path_buf();
+        let manifest = read_manifest(&dir)?;
+        if manifest.package.package_type == Some(PackageType::Library) {
+            manifests.push((normalize_path(&dir), dir, manifest));
+        }
+    }
+
+    let mut members = HashMap::new();
+    for (normalized_dir, _, manifest) in &manifests {
+        let name = verify_and_get_package_name(manifest)?.clone();
+        let version = verify_and_get_version(manifest)?;
+        members.insert(normalized_dir.clone(), (name, version));
+    }
+
+    let mut libraries = Vec::new();
+    for (normalized_dir, dir, manifest) in manifests {
+        let mut internal_dependencies = HashMap::new();
+        for (dependency_name, dependency) in &manifest.dependencies {
+            if let Dependency::Path { path } = dependency {
+                if let Some(member) = members.get(&normalize_path(&dir.join(path))) {
+                    internal_dependencies.insert(dependency_name.clone(), member.clone());
+                }
+            }
+        }
+        let (name, version) = members[&normalized_dir].clone();
+        libraries.push(WorkspaceLibrary { dir, name, version, internal_dependencies });
+    }
+    Ok(libraries)
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Orders libraries so that each library comes after the libraries it depends on.
+fn publish_order<'a>(libraries: &[&'a WorkspaceLibrary]) -> Result<Vec<&'a WorkspaceLibrary>> {
+    let mut ordered: Vec<&WorkspaceLibrary> = Vec::new();
+    let mut remaining: Vec<&WorkspaceLibrary> = libraries.to_vec();
+    while !remaining.is_empty() {
+        let is_ready = |library: &&WorkspaceLibrary| {
+            library.internal_dependencies.values().all(|(name, _)| {
+                ordered.iter().any(|other| &other.name == name) || !remaining.iter().any(|other| &other.name == name)
+            })
+        };
+        let Some(position) = remaining.iter().position(is_ready) else {
+            let names: Vec<&str> = remaining.iter().map(|library| library.name.as_str()).collect();
+            bail!("Workspace members have cyclic dependencies: {}", names.join(", "));
+        };
+        ordered.push(remaining.remove(position));
+    }
+    Ok(ordered)
+}
+
+fn get_api_key() -> Result<String> {
+    match std::env::var(API_KEY_ENV_VAR_NAME) {
+        Ok(api_key) => Ok(api_key),
         Err(_e) => {
             bail!(formatdoc! {
                 "Cannot publish a package. API KEY env variable not found.
@@ -19,28 +215,95 @@ pub fn publish() -> Result<String> {
                  ", format!("{}/dashboard", &REGISTRY_HOME_URL), &API_KEY_ENV_VAR_NAME }
             );
         }
-    };
-
-    let tarball_path = verify_tarball_existence()?;
-    let result_message = publish_package_api(&tarball_path, api_key)?;
-    Ok(result_message)
-}
-
-fn verify_tarball_existence() -> Result<PackagedTarball> {
-    let current_dir = std::env::current_dir()?;
-    let manifest: Manifest = read_manifest(&current_dir)?;
-    let output_folder_path = &current_dir.join(PACKAGING_OUTPUT_FOLDER_PATH);
-    let version = verify_and_get_version(&manifest)?;
-    let package_name = verify_and_get_package_name(&manifest)?;
-    let package = format!("{}_{}", &package_name, &version);
-    let tarball_path = output_folder_path.join(&package).join(format!("{}.tar.gz", &package));
-    println!("Tarball path: {}", tarball_path.display());
-    if !tarball_path.exists() {
-        bail!("Package tarball does not exists. Please run \"noir-libs package\" first.");
-    }
-    Ok(PackagedTarball {
-        tarball_path: tarball_path.to_str().unwrap().to_string(),
-        name: package_name.to_string(),
-        version: version.to_string(),
-    })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::workspace::{find_project, PackageSelection};
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write_member(root: &Path, name: &str, package_type: &str, dependencies: &str) {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join(MANIFEST_FILE_NAME),
+            format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\ntype = \"{}\"\n\n[dependencies]\n{}", name, package_type, dependencies),
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_publish_order() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join(MANIFEST_FILE_NAME), "[workspace]\nmembers = [\"contract\", \"top\", \"middle\", \"base\"]\n").unwrap();
+        write_member(root, "contract", "contract", "top = { path = \"../top\" }\n");
+        write_member(root, "top", "lib", "mid = { path = \"../middle\" }\nbase = { path = \"../base\" }\n");
+        write_member(root, "middle", "lib", "base = { path = \"../base\" }\naztec = { path = \"../.noir-libs-deps/aztec/0.67.0\" }\n");
+        write_member(root, "base", "lib", "");
+        let project = find_project(root, &PackageSelection { workspace: true, package: None }).unwrap();
+
+        let libraries = read_workspace_libraries(&project).unwrap();
+        let selected: Vec<&WorkspaceLibrary> = libraries.iter().collect();
+        let order: Vec<&str> = publish_order(&selected).unwrap().iter().map(|library| library.name.as_str()).collect();
+
+        assert_eq!(order, vec!["base", "middle", "top"]);
+        let top = libraries.iter().find(|library| library.name == "top").unwrap();
+        assert_eq!(top.internal_dependencies["mid"], ("middle".to_string(), "0.1.0".to_string()));
+        let middle = libraries.iter().find(|library| library.name == "middle").unwrap();
+        assert_eq!(middle.internal_dependencies.len(), 1);
+    }
+
+    #[test]
+    fn test_publish_order_cycle() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join(MANIFEST_FILE_NAME), "[workspace]\nmembers = [\"a\", \"b\"]\n").unwrap();
+        write_member(root, "a", "lib", "b = { path = \"../b\" }\n");
+        write_member(root, "b", "lib", "a = { path = \"../a\" }\n");
+        let project = find_project(root, &PackageSelection { workspace: true, package: None }).unwrap();
+
+        let libraries = read_workspace_libraries(&project).unwrap();
+        let selected: Vec<&WorkspaceLibrary> = libraries.iter().collect();
+
+        assert!(publish_order(&selected).unwrap_err().to_string().contains("cyclic"));
+    }
+
+    #[test]
+    fn test_package_order() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join(MANIFEST_FILE_NAME), "[workspace]\nmembers = [\"contract\", \"top\", \"base\"]\n").unwrap();
+        write_member(root, "contract", "contract", "top = { path = \"../top\" }\n");
+        write_member(root, "top", "lib", "base = { path = \"../base\" }\n");
+        write_member(root, "base", "lib", "");
+        let project = find_project(root, &PackageSelection { workspace: true, package: None }).unwrap();
+
+        let order = package_order(&project).unwrap();
+
+        let names: Vec<&str> = order.iter().map(|dir| dir.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, vec!["base", "top", "contract"]);
+    }
+
+    #[test]
+    fn test_publish_rejects_non_library_package() {
+        let temp_dir = tempdir().unwrap();
+        write_member(temp_dir.path(), "my_contract", "contract", "");
+        let project = find_project(&temp_dir.path().join("my_contract"), &PackageSelection::default()).unwrap();
+
+        let result = publish(&project, &PublishOptions { dry_run: true, ..PublishOptions::default() });
+
+        assert!(result.unwrap_err().to_string().contains("package type contract"));
+        assert!(!temp_dir.path().join("my_contract/target").exists());
+    }
+
+    #[test]
+    fn test_already_published_version_is_skipped_only_in_workspace_mode() {
+        let library = WorkspaceLibrary { dir: PathBuf::from("a"), name: "a".to_string(), version: "0.1.0".to_string(), internal_dependencies: HashMap::new() };
+
+        assert!(check_can_skip_published(&library, true).is_ok());
+        assert!(check_can_skip_published(&library, false).unwrap_err().to_string().contains("a 0.1.0 is already published"));
+    }
 }
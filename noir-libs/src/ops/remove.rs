@@ -1,8 +1,120 @@
-use std::env;
+use anyhow::{bail, Context};
 
-use crate::manifest::remove_package;
+use crate::cache::{remove_unreachable_packages, CachedPackage};
+use crate::config::DEPENDENCIES_FOLDER_NAME;
+use crate::manifest::{remove_package, remove_package_if_present};
+use crate::workspace::Project;
 
-pub fn remove(package_name: &str) {
-    let pwd = env::current_dir().expect("Unable to find current folder");
-    remove_package(pwd, package_name);
+/// Removes a package from the dependencies of the selected packages and deletes cached packages
+/// which are no longer reachable from dependencies of any package sharing the dependencies folder.
+///
+/// # Returns
+///
+/// The cached packages that were removed from the dependencies folder.
+pub fn remove(project: &Project, package_name: &str) -> anyhow::Result<Vec<CachedPackage>> {
+    if let [package_dir] = project.packages.as_slice() {
+        remove_package(package_dir.clone(), package_name)?;
+    } else {
+        let mut removed_from = 0;
+        for package_dir in &project.packages {
+            if remove_package_if_present(package_dir.to_path_buf(), package_name)? {
+                removed_from += 1;
+            }
+        }
+        if removed_from == 0 {
+            bail!("Package {} is not a dependency of any selected workspace member. Nothing to remove.", package_name);
+        }
+    }
+
+    let removed = remove_unreachable_packages(&project.cache_root(), &project.all_manifests)
+        .with_context(|| format!("Failed to clean up {} folder", &DEPENDENCIES_FOLDER_NAME))?;
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MANIFEST_FILE_NAME;
+    use crate::workspace::{find_project, PackageSelection};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn project(dir: &Path) -> Project {
+        find_project(dir, &PackageSelection::default()).unwrap()
+    }
+
+    #[test]
+    fn test_remove_cleans_up_orphaned_packages() {
+        let temp_dir = tempdir().unwrap();
+        let project_dir = temp_dir.path();
+        let cache_root = project_dir.join(DEPENDENCIES_FOLDER_NAME);
+        fs::write(
+            project_dir.join(MANIFEST_FILE_NAME),
+            "[dependencies]\na = { path = \".noir-libs-deps/a/1.0.0\" }\nc = { path = \".noir-libs-deps/c/1.0.0\" }\n",
+        ).unwrap();
+        fs::create_dir_all(cache_root.join("a/1.0.0")).unwrap();
+        fs::write(cache_root.join("a/1.0.0").join(MANIFEST_FILE_NAME), "[dependencies]\nb = { path = \"../../b/1.0.0\" }\n").unwrap();
+        fs::create_dir_all(cache_root.join("b/1.0.0")).unwrap();
+        fs::create_dir_all(cache_root.join("c/1.0.0")).unwrap();
+        fs::write(cache_root.join("c/1.0.0").join(MANIFEST_FILE_NAME), "[dependencies]\nb = { path = \"../../b/1.0.0\" }\n").unwrap();
+
+        let removed = remove(&project(project_dir), "a").unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(!cache_root.join("a").exists());
+        assert!(cache_root.join("b/1.0.0").is_dir());
+
+        let removed = remove(&project(project_dir), "c").unwrap();
+        assert_eq!(removed.len(), 2);
+        assert!(!cache_root.join("b").exists());
+        assert!(!cache_root.join("c").exists());
+    }
+
+    #[test]
+    fn test_remove_unknown_package() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), "[dependencies]\n").unwrap();
+
+        let result = remove(&project(temp_dir.path()), "unknown");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_remove_keeps_packages_used_by_other_workspace_members() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        let cache_root = root.join(DEPENDENCIES_FOLDER_NAME);
+        fs::write(root.join(MANIFEST_FILE_NAME), "[workspace]\nmembers = [\"a\", \"b\"]\n").unwrap();
+        for member in ["a", "b"] {
+            fs::create_dir_all(root.join(member)).unwrap();
+            fs::write(
+                root.join(member).join(MANIFEST_FILE_NAME),
+                format!("[package]\nname = \"{}\"\n\n[dependencies]\naztec = {{ path = \"../.noir-libs-deps/aztec/1.0.0\" }}\n", member),
+            ).unwrap();
+        }
+        fs::create_dir_all(cache_root.join("aztec/1.0.0")).unwrap();
+
+        let removed = remove(&project(&root.join("a")), "aztec").unwrap();
+        assert!(removed.is_empty());
+        assert!(cache_root.join("aztec/1.0.0").is_dir());
+
+        let removed = remove(&project(&root.join("b")), "aztec").unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(!cache_root.join("aztec").exists());
+    }
+
+    #[test]
+    fn test_remove_from_workspace_propagates_manifest_errors() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join(MANIFEST_FILE_NAME), "[workspace]\nmembers = [\"a\", \"b\"]\n").unwrap();
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::write(root.join("a").join(MANIFEST_FILE_NAME), "[package]\nname = \"a\"\n\n[dependencies]\naztec = { path = \"../.noir-libs-deps/aztec/1.0.0\" }\n").unwrap();
+        let workspace = project(root);
+        fs::create_dir_all(root.join("b")).unwrap();
+        fs::write(root.join("b").join(MANIFEST_FILE_NAME), "[dependencies\n").unwrap();
+
+        let result = remove(&workspace, "aztec");
+        assert!(result.unwrap_err().to_string().contains("invalid TOML"));
+    }
 }
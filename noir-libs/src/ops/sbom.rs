@@ -0,0 +1,425 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{bail, Result};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use time::OffsetDateTime;
+
+use crate::cache::{get_stored_dependencies, resolve_graph, GitDependency};
+use crate::config::{MANIFEST_FILE_NAME, REGISTRY_URL};
+use crate::filesystem::prepare_cache_dir;
+use crate::manifest::read_manifest;
+use crate::metadata::read_metadata;
+use crate::signing::package_digest;
+use crate::tar::SOURCE_DATE_EPOCH_ENV_VAR_NAME;
+use crate::workspace::Project;
+
+const TOOL_NAME: &str = "noir-libs";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SbomFormat {
+    CycloneDx,
+    Spdx,
+}
+
+impl FromStr for SbomFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format.to_lowercase().as_str() {
+            "cyclonedx" => Ok(SbomFormat::CycloneDx),
+            "spdx" => Ok(SbomFormat::Spdx),
+            _ => Err(format!("Unknown SBOM format {}, expected cyclonedx or spdx", format)),
+        }
+    }
+}
+
+/// A package of the project described by the SBOM
+#[derive(Debug, Clone)]
+pub struct SbomComponent {
+    pub name: String,
+    pub version: String,
+    pub license: Option<String>,
+    /// Registry the package was downloaded from, `None` for the project packages
+    pub registry: Option<String>,
+    /// Hex encoded SHA-256 digest of the stored package contents, see [`package_digest`]
+    pub sha256: Option<String>,
+    /// Source of a git dependency, `None` for other packages
+    pub vcs: Option<VcsSource>,
+    /// Dependencies as (package name, version) pairs
+    pub dependencies: Vec<(String, String)>,
+}
+
+/// Git repository a dependency is built from
+#[derive(Debug, Clone)]
+pub struct VcsSource {
+    pub url: String,
+    pub tag: Option<String>,
+    /// Package subdirectory inside the repository
+    pub directory: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sbom {
+    /// The selected project packages
+    pub roots: Vec<SbomComponent>,
+    /// All resolved dependencies
+    pub components: Vec<SbomComponent>,
+    /// Creation time in RFC 3339 format
+    pub created: String,
+}
+
+/// Writes the resolved dependency graph of the selected packages as a JSON SBOM.
+///
+/// # Arguments
+///
+/// * `project` - The selected packages.
+/// * `format` - CycloneDX 1.5 or SPDX 2.3 JSON.
+pub fn sbom(project: &Project, format: SbomFormat) -> Result<String> {
+    let sbom = collect_sbom(project)?;
+    let document = match format {
+        SbomFormat::CycloneDx => to_cyclonedx(&sbom),
+        SbomFormat::Spdx => to_spdx(&sbom),
+    };
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+/// Collects names, versions, licenses, sources and checksums of the project packages and their resolved dependencies.
+/// All dependencies must be fetched. Git dependencies are listed with their repository, they have no checksum.
+pub fn collect_sbom(project: &Project) -> Result<Sbom> {
+    let cache_root = prepare_cache_dir(&project.root);
+    let manifests: Vec<PathBuf> = project.packages.iter().map(|dir| dir.join(MANIFEST_FILE_NAME)).collect();
+    let graph = resolve_graph(&cache_root, &manifests);
+    let mut roots = Vec::new();
+    for package_dir in &project.packages {
+        let manifest = read_manifest(package_dir)?;
+        let manifest_path = package_dir.join(MANIFEST_FILE_NAME);
+        let mut dependencies = get_stored_dependencies(&manifest_path, &cache_root);
+        dependencies.extend(graph.git_dependencies.iter()
+            .filter(|dependency| dependency.manifest == manifest_path)
+            .map(|dependency| (dependency.name.clone(), dependency.tag.clone().unwrap_or_default())));
+        roots.push(SbomComponent {
+            name: manifest.package.name.clone().unwrap_or_default(),
+            version: manifest.package.version.clone().unwrap_or_default(),
+            license: manifest.package.license.clone(),
+            registry: None,
+            sha256: None,
+            vcs: None,
+            dependencies,
+        });
+    }
+
+    let mut components = Vec::new();
+    for package in graph.packages {
+        if !package.dir.join(MANIFEST_FILE_NAME).is_file() {
+            bail!("Package {}@{} is not fetched. Run \"noir-libs fetch\" first.", package.name, package.version);
+        }
+        let manifest = read_manifest(&package.dir)?;
+        let registry = read_metadata(&package.dir)?.map_or(REGISTRY_URL.to_string(), |metadata| metadata.registry);
+        components.push(SbomComponent {
+            license: manifest.package.license,
+            registry: Some(registry),
+            // the downloaded archive may be pruned or not exist for vendored packages, the contents are always available
+            sha256: Some(hex::encode(package_digest(&package.dir)?)),
+            vcs: None,
+            name: package.name,
+            version: package.version,
+            dependencies: package.dependencies,
+        });
+    }
+    for dependency in &graph.git_dependencies {
+        if !components.iter().any(|component| is_same_git_component(component, dependency)) {
+            components.push(git_component(dependency));
+        }
+    }
+    Ok(Sbom { roots, components, created: creation_time() })
+}
+
+fn git_component(dependency: &GitDependency) -> SbomComponent {
+    SbomComponent {
+        name: dependency.name.clone(),
+        version: dependency.tag.clone().unwrap_or_default(),
+        license: None,
+        registry: None,
+        sha256: None,
+        vcs: Some(VcsSource { url: dependency.git.clone(), tag: dependency.tag.clone(), directory: dependency.directory.clone() }),
+        dependencies: Vec::new(),
+    }
+}
+
+fn is_same_git_component(component: &SbomComponent, dependency: &GitDependency) -> bool {
+    component.vcs.as_ref().is_some_and(|vcs| vcs.url == dependency.git && vcs.tag == dependency.tag && vcs.directory == dependency.directory)
+        && component.name == dependency.name
+}
+
+/// The creation time is taken from `SOURCE_DATE_EPOCH` if set, so the SBOM can be reproduced
+fn creation_time() -> String {
+    let time = std::env::var(SOURCE_DATE_EPOCH_ENV_VAR_NAME)
+        .ok()
+        .and_then(|epoch| epoch.trim().parse::<i64>().ok())
+        .and_then(|epoch| OffsetDateTime::from_unix_timestamp(epoch).ok())
+        .unwrap_or_else(OffsetDateTime::now_utc);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", time.year(), time.month() as u8, time.day(), time.hour(), time.minute(), time.second())
+}
+
+/// Example: https://api.noir-libs.org/api/v1/packages/aztec/0.67.0/download
+fn download_url(component: &SbomComponent) -> Option<String> {
+    component.registry.as_ref().map(|registry| format!("{}/packages/{}/{}/download", registry, component.name, component.version))
+}
+
+/// SPDX download location of a git dependency
+/// Example: git+https://github.com/AztecProtocol/aztec-packages@v0.67.0#noir-projects/aztec-nr/aztec
+fn vcs_download_location(vcs: &VcsSource) -> String {
+    let mut location = format!("git+{}", vcs.url);
+    if let Some(tag) = &vcs.tag {
+        location.push_str(&format!("@{}", tag));
+    }
+    if let Some(directory) = &vcs.directory {
+        location.push_str(&format!("#{}", directory));
+    }
+    location
+}
+
+/// Example: aztec@0.67.0, a project package without a version is referenced by its name
+fn bom_ref(name: &str, version: &str) -> String {
+    if version.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}@{}", name, version)
+    }
+}
+
+/// CycloneDX 1.5 JSON document
+pub fn to_cyclonedx(sbom: &Sbom) -> Value {
+    let cyclonedx_component = |component: &SbomComponent, component_type: &str| {
+        let mut value = json!({
+            "type": component_type,
+            "bom-ref": bom_ref(&component.name, &component.version),
+            "name": component.name,
+        });
+        if !component.version.is_empty() {
+            value["version"] = json!(component.version);
+        }
+        if let Some(license) = &component.license {
+            value["licenses"] = json!([{ "expression": license }]);
+        }
+        if let Some(sha256) = &component.sha256 {
+            value["hashes"] = json!([{ "alg": "SHA-256", "content": sha256 }]);
+        }
+        if let Some(url) = download_url(component) {
+            value["externalReferences"] = json!([{ "type": "distribution", "url": url }]);
+        }
+        if let Some(vcs) = &component.vcs {
+            value["externalReferences"] = json!([{ "type": "vcs", "url": vcs.url, "comment": vcs_download_location(vcs) }]);
+        }
+        value
+    };
+
+    let mut metadata = json!({
+        "timestamp": sbom.created,
+        "tools": { "components": [{ "type": "application", "name": TOOL_NAME, "version": env!("CARGO_PKG_VERSION") }] },
+    });
+    let mut components: Vec<Value> = sbom.components.iter().map(|component| cyclonedx_component(component, "library")).collect();
+    match sbom.roots.as_slice() {
+        [root] => metadata["component"] = cyclonedx_component(root, "application"),
+        roots => components.extend(roots.iter().map(|root| cyclonedx_component(root, "application"))),
+    }
+    let dependencies: Vec<Value> = sbom.roots.iter().chain(&sbom.components)
+        .map(|component| json!({
+            "ref": bom_ref(&component.name, &component.version),
+            "dependsOn": component.dependencies.iter().map(|(name, version)| bom_ref(name, version)).collect::<Vec<_>>(),
+        }))
+        .collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": metadata,
+        "components": components,
+        "dependencies": dependencies,
+    })
+}
+
+/// SPDX identifiers may contain only letters, digits, `.` and `-`
+/// Example: value_note@0.67.0 -> SPDXRef-Package-value-note-0.67.0
+fn spdx_id(name: &str, version: &str) -> String {
+    let id: String = bom_ref(name, version)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' })
+        .collect();
+    format!("SPDXRef-Package-{}", id)
+}
+
+/// SPDX 2.3 JSON document
+pub fn to_spdx(sbom: &Sbom) -> Value {
+    let packages: Vec<Value> = sbom.roots.iter().chain(&sbom.components)
+        .map(|component| {
+            let mut value = json!({
+                "SPDXID": spdx_id(&component.name, &component.version),
+                "name": component.name,
+                "downloadLocation": download_url(component)
+                    .or_else(|| component.vcs.as_ref().map(vcs_download_location))
+                    .unwrap_or_else(|| "NOASSERTION".to_string()),
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": component.license.as_deref().unwrap_or("NOASSERTION"),
+                "copyrightText": "NOASSERTION",
+                "filesAnalyzed": false,
+            });
+            if !component.version.is_empty() {
+                value["versionInfo"] = json!(component.version);
+            }
+            if let Some(sha256) = &component.sha256 {
+                value["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": sha256 }]);
+            }
+            value
+        })
+        .collect();
+
+    let mut relationships: Vec<Value> = sbom.roots.iter()
+        .map(|root| json!({
+            "spdxElementId": "SPDXRef-DOCUMENT",
+            "relationshipType": "DESCRIBES",
+            "relatedSpdxElement": spdx_id(&root.name, &root.version),
+        }))
+        .collect();
+    for component in sbom.roots.iter().chain(&sbom.components) {
+        relationships.extend(component.dependencies.iter().map(|(name, version)| json!({
+            "spdxElementId": spdx_id(&component.name, &component.version),
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": spdx_id(name, version),
+        })));
+    }
+
+    let name = sbom.roots.iter().map(|root| root.name.as_str()).collect::<Vec<_>>().join("+");
+    // the namespace must be unique for each document, derived from its contents so it is reproducible
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_string(&packages).unwrap_or_default());
+    hasher.update(&sbom.created);
+    let namespace = format!("https://noir-libs.org/spdx/{}-{}", name, &hex::encode(hasher.finalize())[..16]);
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": namespace,
+        "creationInfo": {
+            "created": sbom.created,
+            "creators": [format!("Tool: {}-{}", TOOL_NAME, env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEPENDENCIES_FOLDER_NAME;
+    use crate::path::get_cache_storage;
+    use crate::workspace::{find_project, PackageSelection};
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn write_package(cache_root: &Path, name: &str, version: &str, license: &str, dependencies: &str) {
+        let dir = cache_root.join(name).join(version);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(MANIFEST_FILE_NAME), format!("[package]\nname = \"{}\"\nlicense = \"{}\"\n\n[dependencies]\n{}", name, license, dependencies)).unwrap();
+        fs::write(get_cache_storage(cache_root.to_path_buf(), name, version), format!("{} archive", name)).unwrap();
+    }
+
+    fn write_project(project_dir: &Path) {
+        let cache_root = project_dir.join(DEPENDENCIES_FOLDER_NAME);
+        write_package(&cache_root, "value_note", "0.67.0", "Apache-2.0", "aztec = { path = \"../../aztec/0.67.0\" }\n");
+        write_package(&cache_root, "aztec", "0.67.0", "MIT", "");
+        fs::write(project_dir.join(MANIFEST_FILE_NAME), "[package]\nname = \"my_contract\"\nversion = \"1.0.0\"\ntype = \"contract\"\n\n[dependencies]\nvalue_note = { path = \".noir-libs-deps/value_note/0.67.0\" }\n").unwrap();
+    }
+
+    #[test]
+    fn test_cyclonedx() {
+        let temp_dir = tempdir().unwrap();
+        write_project(temp_dir.path());
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+
+        let document = to_cyclonedx(&collect_sbom(&project).unwrap());
+
+        assert_eq!(document["bomFormat"], "CycloneDX");
+        assert_eq!(document["metadata"]["component"]["name"], "my_contract");
+        let components = document["components"].as_array().unwrap();
+        assert_eq!(components.len(), 2);
+        assert_eq!(components[0]["name"], "aztec");
+        assert_eq!(components[0]["licenses"][0]["expression"], "MIT");
+        let aztec_dir = temp_dir.path().join(DEPENDENCIES_FOLDER_NAME).join("aztec/0.67.0");
+        assert_eq!(components[0]["hashes"][0]["content"], hex::encode(package_digest(&aztec_dir).unwrap()));
+        assert_eq!(components[0]["externalReferences"][0]["url"], format!("{}/packages/aztec/0.67.0/download", REGISTRY_URL));
+        assert!(document["dependencies"].as_array().unwrap().contains(&json!({ "ref": "value_note@0.67.0", "dependsOn": ["aztec@0.67.0"] })));
+        assert!(document["dependencies"].as_array().unwrap().contains(&json!({ "ref": "my_contract@1.0.0", "dependsOn": ["value_note@0.67.0"] })));
+    }
+
+    #[test]
+    fn test_spdx() {
+        let temp_dir = tempdir().unwrap();
+        write_project(temp_dir.path());
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+
+        let document = to_spdx(&collect_sbom(&project).unwrap());
+
+        assert_eq!(document["spdxVersion"], "SPDX-2.3");
+        let packages = document["packages"].as_array().unwrap();
+        assert_eq!(packages.len(), 3);
+        assert_eq!(packages[2]["SPDXID"], "SPDXRef-Package-value-note-0.67.0");
+        assert_eq!(packages[2]["licenseDeclared"], "Apache-2.0");
+        assert_eq!(packages[2]["checksums"][0]["algorithm"], "SHA256");
+        assert!(document["relationships"].as_array().unwrap().contains(&json!({
+            "spdxElementId": "SPDXRef-Package-value-note-0.67.0",
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": "SPDXRef-Package-aztec-0.67.0",
+        })));
+    }
+
+    #[test]
+    fn test_sbom_requires_fetched_dependencies() {
+        let temp_dir = tempdir().unwrap();
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), "[package]\nname = \"my_contract\"\n\n[dependencies]\naztec = { path = \".noir-libs-deps/aztec/0.67.0\" }\n").unwrap();
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+
+        assert!(collect_sbom(&project).unwrap_err().to_string().contains("is not fetched"));
+    }
+
+    #[test]
+    fn test_sbom_checksums_without_archives_and_git_dependencies() {
+        let temp_dir = tempdir().unwrap();
+        write_project(temp_dir.path());
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), "[package]\nname = \"my_contract\"\nversion = \"1.0.0\"\n\n[dependencies]\n\
+            value_note = { path = \".noir-libs-deps/value_note/0.67.0\" }\n\
+            aztec_git = { git = \"https://github.com/AztecProtocol/aztec-packages\", tag = \"v0.67.0\", directory = \"noir-projects/aztec-nr/aztec\" }\n").unwrap();
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+        let with_archives = collect_sbom(&project).unwrap();
+        // archives are removed e.g. by "noir-libs cache prune"
+        let cache_root = temp_dir.path().join(DEPENDENCIES_FOLDER_NAME);
+        for name in ["aztec", "value_note"] {
+            fs::remove_file(get_cache_storage(cache_root.clone(), name, "0.67.0")).unwrap();
+        }
+
+        let sbom = collect_sbom(&project).unwrap();
+
+        let checksums = |sbom: &Sbom| sbom.components.iter().map(|component| component.sha256.clone()).collect::<Vec<_>>();
+        assert_eq!(checksums(&sbom), checksums(&with_archives));
+        assert!(sbom.components[..2].iter().all(|component| component.sha256.is_some()));
+        let document = to_spdx(&sbom);
+        let git_package = document["packages"].as_array().unwrap().iter().find(|package| package["name"] == "aztec_git").unwrap();
+        assert_eq!(git_package["downloadLocation"], "git+https://github.com/AztecProtocol/aztec-packages@v0.67.0#noir-projects/aztec-nr/aztec");
+        assert!(document["relationships"].as_array().unwrap().contains(&json!({
+            "spdxElementId": "SPDXRef-Package-my-contract-1.0.0",
+            "relationshipType": "DEPENDS_ON",
+            "relatedSpdxElement": "SPDXRef-Package-aztec-git-v0.67.0",
+        })));
+        let document = to_cyclonedx(&sbom);
+        let git_component = document["components"].as_array().unwrap().iter().find(|component| component["name"] == "aztec_git").unwrap();
+        assert_eq!(git_component["externalReferences"][0]["type"], "vcs");
+        assert_eq!(git_component["version"], "v0.67.0");
+    }
+}
@@ -0,0 +1,439 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::cache::{get_stored_dependency_dirs, get_stored_package, normalize_path, resolve_stored_packages};
+use crate::config::{MANIFEST_FILE_NAME, VENDOR_CHECKSUM_FILE_NAME};
+use crate::filesystem::prepare_cache_dir;
+use crate::manifest::{read_manifest, write_package_dep, Dependency};
+use crate::ops::add::store_package;
+use crate::ops::options::ResolveOptions;
+use crate::path::get_package_dir;
+use crate::workspace::Project;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VendorOptions {
+    pub resolve: ResolveOptions,
+    /// Replace a vendor folder even if vendored files were changed since vendoring
+    pub force: bool,
+}
+
+/// Checksum manifest written into the vendor folder
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VendorChecksums {
+    /// Vendored packages as name@version
+    pub packages: Vec<String>,
+    /// SHA-256 of each vendored file by its path relative to the vendor folder
+    pub files: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default)]
+pub struct VendorReport {
+    pub packages: Vec<String>,
+    /// Manifests with dependencies rewritten to the vendor folder
+    pub rewritten_manifests: Vec<PathBuf>,
+}
+
+/// Copies the resolved dependency tree of the selected packages into the vendor folder
+/// with the `<package>/<version>` layout, rewrites their path dependencies to point into it
+/// and writes a checksum manifest. Vendored packages build without the registry.
+///
+/// Dependencies already pointing into this or another vendor folder are vendored again, from the
+/// dependencies folder if it has them, otherwise from the vendor folder, so re-vendoring works offline
+/// with an empty dependencies folder. Missing packages are downloaded. Git dependencies cannot be
+/// vendored, and vendoring nothing is an error. An existing vendor folder is replaced only if it was
+/// created by vendor and its files match the checksums (or `force` is set). The new folder is built
+/// next to the old one and renamed into place, so a failure never leaves manifests pointing to a
+/// removed folder.
+///
+/// # Arguments
+///
+/// * `project` - The selected packages.
+/// * `vendor_dir` - Absolute path of the vendor folder.
+/// * `options` - Vendor options.
+pub fn vendor(project: &Project, vendor_dir: &Path, options: &VendorOptions) -> Result<VendorReport> {
+    let vendor_dir = normalize_path(vendor_dir);
+    let cache_root = prepare_cache_dir(&project.root);
+    check_vendor_dir_location(project, &cache_root, &vendor_dir)?;
+    check_vendor_dir_replaceable(&vendor_dir, options.force)?;
+
+    let mut git_dependencies = Vec::new();
+    let mut roots = BTreeSet::new();
+    let mut manifests_to_rewrite = Vec::new();
+    for package_dir in &project.packages {
+        let manifest = package_dir.join(MANIFEST_FILE_NAME);
+        for (dependency_name, dependency) in &read_manifest(package_dir)?.dependencies {
+            if let Dependency::Git { git, .. } = dependency {
+                git_dependencies.push(format!("{} = {{ git = \"{}\" }} in {}", dependency_name, git, manifest.display()));
+            }
+        }
+        // dependencies in the dependencies folder or in another vendor folder are moved to this vendor folder
+        let to_rewrite = get_dependencies_outside_folder(&manifest, &cache_root, &vendor_dir)?;
+        if !to_rewrite.is_empty() {
+            manifests_to_rewrite.push((manifest.clone(), to_rewrite));
+        }
+        roots.extend(get_stored_dependency_dirs(&manifest, &cache_root));
+    }
+    if !git_dependencies.is_empty() {
+        bail!("Git dependencies cannot be vendored, replace them with registry packages using \"noir-libs migrate\":\n{}", git_dependencies.join("\n"));
+    }
+    if options.resolve.locked && !manifests_to_rewrite.is_empty() {
+        bail!("Vendoring would modify {}, but --locked or --frozen was passed", MANIFEST_FILE_NAME);
+    }
+
+    let mut root_packages = Vec::new();
+    for (package_name, version, dir) in roots {
+        let cached_dir = get_package_dir(cache_root.clone(), &package_name, &version);
+        let is_vendored = !dir.starts_with(&cache_root);
+        if is_vendored && !cached_dir.join(MANIFEST_FILE_NAME).is_file() {
+            // already vendored and not in the cache, sub-dependencies are vendored next to it
+            root_packages.push((package_name, version, dir));
+            continue;
+        }
+        // stores missing packages with their sub-dependencies in the cache
+        store_package(cache_root.clone(), &package_name, &version, true, &options.resolve)
+            .map_err(|e| anyhow::anyhow!("Failed to fetch {}@{}: {}", package_name, version, e))?;
+        root_packages.push((package_name, version, cached_dir));
+    }
+    let graph = resolve_stored_packages(root_packages);
+    if !graph.unresolved.is_empty() {
+        bail!("Unable to resolve dependencies:\n{}", graph.unresolved.join("\n"));
+    }
+    if graph.packages.is_empty() {
+        bail!("No dependencies to vendor. Only dependencies added with \"noir-libs add\" or already vendored can be vendored.");
+    }
+
+    // built in a temporary sibling folder, removed automatically if anything fails
+    let parent = vendor_dir.parent().context("The vendor folder must not be the filesystem root")?;
+    fs::create_dir_all(parent)?;
+    let staging = tempfile::Builder::new().prefix(".noir-libs-vendor-").tempdir_in(parent)
+        .with_context(|| format!("Cannot create a temporary folder in {}", parent.display()))?;
+    let mut report = VendorReport::default();
+    for package in &graph.packages {
+        copy_dir(&package.dir, &get_package_dir(staging.path().to_path_buf(), &package.name, &package.version))?;
+        report.packages.push(format!("{}@{}", package.name, package.version));
+    }
+    let checksums = VendorChecksums { packages: report.packages.clone(), files: checksum_files(staging.path())? };
+    fs::write(staging.path().join(VENDOR_CHECKSUM_FILE_NAME), serde_json::to_string_pretty(&checksums)? + "\n")?;
+    replace_dir(staging, &vendor_dir)?;
+
+    for (manifest, dependencies) in manifests_to_rewrite {
+        let manifest_dir = manifest.parent().expect("Failed to get manifest parent directory");
+        for (dependency_name, package_name, version) in dependencies {
+            let vendored_path = get_package_dir(vendor_dir.clone(), &package_name, &version);
+            let relative_path = pathdiff::diff_paths(&vendored_path, manifest_dir).unwrap_or(vendored_path);
+            write_package_dep(&manifest, &dependency_name, &relative_path.to_string_lossy().replace('\\', "/"));
+        }
+        report.rewritten_manifests.push(manifest);
+    }
+    Ok(report)
+}
+
+/// Compares files of the vendor folder with its checksum manifest.
+///
+/// # Returns
+///
+/// Modified, added and removed files, empty if the vendor folder has not drifted.
+pub fn check_vendor(vendor_dir: &Path) -> Result<Vec<String>> {
+    let checksum_path = vendor_dir.join(VENDOR_CHECKSUM_FILE_NAME);
+    let content = fs::read_to_string(&checksum_path)
+        .with_context(|| format!("Cannot read {}. Is {} a vendor folder?", checksum_path.display(), vendor_dir.display()))?;
+    let expected: VendorChecksums = serde_json::from_str(&content).with_context(|| format!("{} is invalid", checksum_path.display()))?;
+    let actual = checksum_files(vendor_dir)?;
+
+    let mut drift = Vec::new();
+    for (path, checksum) in &expected.files {
+        match actual.get(path) {
+            Some(actual_checksum) if actual_checksum == checksum => {}
+            Some(_) => drift.push(format!("modified: {}", path)),
+            None => drift.push(format!("removed: {}", path)),
+        }
+    }
+    drift.extend(actual.keys().filter(|path| !expected.files.contains_key(*path)).map(|path| format!("added: {}", path)));
+    Ok(drift)
+}
+
+/// The vendor folder is replaced as a whole, so it must not contain the project, its dependencies folder
+/// or sources of its packages, and must not be inside the dependencies folder
+fn check_vendor_dir_location(project: &Project, cache_root: &Path, vendor_dir: &Path) -> Result<()> {
+    let cache_root = normalize_path(cache_root);
+    if normalize_path(&project.root).starts_with(vendor_dir) {
+        bail!("{} is the project folder or one of its parent folders and cannot be used as the vendor folder", vendor_dir.display());
+    }
+    if vendor_dir.starts_with(&cache_root) {
+        bail!("{} is inside the dependencies folder {} and cannot be used as the vendor folder", vendor_dir.display(), cache_root.display());
+    }
+    for manifest in &project.all_manifests {
+        let package_dir = normalize_path(manifest.parent().expect("Failed to get manifest parent directory"));
+        if package_dir.starts_with(vendor_dir) {
+            bail!("{} contains the package {} and cannot be used as the vendor folder", vendor_dir.display(), package_dir.display());
+        }
+    }
+    Ok(())
+}
+
+/// An existing vendor folder may be replaced if it has no local changes (or `force` is set),
+/// other folders only if empty. A folder without the checksum manifest is never replaced.
+fn check_vendor_dir_replaceable(vendor_dir: &Path, force: bool) -> Result<()> {
+    if !vendor_dir.exists() {
+        return Ok(());
+    }
+    if !vendor_dir.join(VENDOR_CHECKSUM_FILE_NAME).is_file() {
+        if !vendor_dir.is_dir() || fs::read_dir(vendor_dir)?.next().is_some() {
+            bail!("{} is not empty and was not created by vendor. Remove it or choose another vendor folder.", vendor_dir.display());
+        }
+        return Ok(());
+    }
+    let drift = check_vendor(vendor_dir)?;
+    if !drift.is_empty() && !force {
+        bail!("Vendored files were changed since vendoring, use --force to discard the changes:\n{}", drift.join("\n"));
+    }
+    Ok(())
+}
+
+/// Moves the built folder to the destination, replacing the existing folder
+fn replace_dir(built: tempfile::TempDir, dest: &Path) -> Result<()> {
+    if !dest.exists() {
+        fs::rename(built.path(), dest).with_context(|| format!("Cannot move the vendored packages to {}", dest.display()))?;
+        let _ = built.into_path();
+        return Ok(());
+    }
+    // the old folder is moved aside first, so it can be restored if the new one cannot be moved into place
+    let parent = dest.parent().context("The vendor folder must not be the filesystem root")?;
+    let old = tempfile::Builder::new().prefix(".noir-libs-vendor-old-").tempdir_in(parent)?;
+    let old_dir = old.path().join("vendor");
+    fs::rename(dest, &old_dir).with_context(|| format!("Cannot replace {}", dest.display()))?;
+    if let Err(e) = fs::rename(built.path(), dest) {
+        fs::rename(&old_dir, dest).ok();
+        return Err(e).with_context(|| format!("Cannot move the vendored packages to {}", dest.display()));
+    }
+    let _ = built.into_path();
+    Ok(())
+}
+
+/// Dependencies of a manifest pointing to packages stored outside of the given folder,
+/// in the dependencies folder or in a vendor folder
+///
+/// # Returns
+///
+/// (dependency name, package name, version) tuples.
+fn get_dependencies_outside_folder(manifest: &Path, cache_root: &Path, folder: &Path) -> Result<Vec<(String, String, String)>> {
+    let manifest_dir = manifest.parent().expect("Failed to get manifest parent directory");
+    let content = fs::read_to_string(manifest)?;
+    let doc = content.parse::<toml_edit::DocumentMut>()?;
+    Ok(doc.get("dependencies")
+        .and_then(|dependencies| dependencies.as_table_like())
+        .map(|dependencies| dependencies.iter()
+            .filter_map(|(name, dependency)| {
+                let package_dir = normalize_path(&manifest_dir.join(dependency.get("path")?.as_str()?));
+                if package_dir.starts_with(folder) {
+                    return None;
+                }
+                let (package_name, version) = get_stored_package(&package_dir, cache_root)?;
+                Some((name.to_string(), package_name, version))
+            })
+            .collect())
+        .unwrap_or_default())
+}
+
+fn copy_dir(src: &Path, dest: &Path) -> Result<()> {
+    for entry in walkdir::WalkDir::new(src) {
+        let entry = entry?;
+        let target = dest.join(entry.path().strip_prefix(src)?);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// SHA-256 of all files in the vendor folder except the checksum manifest, by their relative path
+fn checksum_files(vendor_dir: &Path) -> Result<BTreeMap<String, String>> {
+    let mut checksums = BTreeMap::new();
+    for entry in walkdir::WalkDir::new(vendor_dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative_path = entry.path().strip_prefix(vendor_dir)?;
+        if relative_path == Path::new(VENDOR_CHECKSUM_FILE_NAME) {
+            continue;
+        }
+        let path: Vec<String> = relative_path.components().map(|component| component.as_os_str().to_string_lossy().to_string()).collect();
+        checksums.insert(path.join("/"), hex::encode(Sha256::digest(fs::read(entry.path())?)));
+    }
+    Ok(checksums)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEPENDENCIES_FOLDER_NAME;
+    use crate::workspace::{find_project, PackageSelection};
+    use tempfile::tempdir;
+
+    const OFFLINE: VendorOptions = VendorOptions { resolve: ResolveOptions { offline: true, locked: false }, force: false };
+
+    fn write_project(project_dir: &Path) {
+        let cache_root = project_dir.join(DEPENDENCIES_FOLDER_NAME);
+        for (name, dependencies) in [("value_note", "aztec = { path = \"../../aztec/0.67.0\" }\n"), ("aztec", "")] {
+            let dir = cache_root.join(name).join("0.67.0");
+            fs::create_dir_all(dir.join("src")).unwrap();
+            fs::write(dir.join("src/lib.nr"), format!("// {}", name)).unwrap();
+            fs::write(dir.join(MANIFEST_FILE_NAME), format!("[package]\nname = \"{}\"\n\n[dependencies]\n{}", name, dependencies)).unwrap();
+        }
+        fs::write(project_dir.join(MANIFEST_FILE_NAME), indoc::indoc! {r#"
+            [package]
+            name = "my_contract"
+            type = "contract"
+
+            [dependencies]
+            note = { path = ".noir-libs-deps/value_note/0.67.0" }
+            local = { path = "../local" }
+        "#}).unwrap();
+    }
+
+    #[test]
+    fn test_vendor() {
+        let temp_dir = tempdir().unwrap();
+        write_project(temp_dir.path());
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+        let vendor_dir = temp_dir.path().join("vendor");
+
+        let report = vendor(&project, &vendor_dir, &OFFLINE).unwrap();
+
+        assert_eq!(report.packages, vec!["aztec@0.67.0".to_string(), "value_note@0.67.0".to_string()]);
+        assert!(vendor_dir.join("aztec/0.67.0/src/lib.nr").is_file());
+        assert!(vendor_dir.join("value_note/0.67.0/Nargo.toml").is_file());
+        let manifest = fs::read_to_string(temp_dir.path().join(MANIFEST_FILE_NAME)).unwrap();
+        assert!(manifest.contains("note = { path = \"vendor/value_note/0.67.0\" }"));
+        assert!(manifest.contains("local = { path = \"../local\" }"));
+        let checksums: VendorChecksums = serde_json::from_str(&fs::read_to_string(vendor_dir.join(VENDOR_CHECKSUM_FILE_NAME)).unwrap()).unwrap();
+        assert_eq!(checksums.files.len(), 4);
+        assert!(check_vendor(&vendor_dir).unwrap().is_empty());
+
+        // vendoring again uses the dependencies pointing into the vendor folder
+        let report = vendor(&project, &vendor_dir, &OFFLINE).unwrap();
+        assert_eq!(report.packages.len(), 2);
+        assert!(report.rewritten_manifests.is_empty());
+    }
+
+    #[test]
+    fn test_vendor_again_without_cached_packages() {
+        let temp_dir = tempdir().unwrap();
+        write_project(temp_dir.path());
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+        let vendor_dir = temp_dir.path().join("vendor");
+        vendor(&project, &vendor_dir, &OFFLINE).unwrap();
+        fs::remove_dir_all(temp_dir.path().join(DEPENDENCIES_FOLDER_NAME)).unwrap();
+        fs::create_dir(temp_dir.path().join(DEPENDENCIES_FOLDER_NAME)).unwrap();
+
+        let report = vendor(&project, &vendor_dir, &OFFLINE).unwrap();
+
+        assert_eq!(report.packages, vec!["aztec@0.67.0".to_string(), "value_note@0.67.0".to_string()]);
+        assert!(vendor_dir.join("aztec/0.67.0/src/lib.nr").is_file());
+        assert!(check_vendor(&vendor_dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_vendor_into_another_folder() {
+        let temp_dir = tempdir().unwrap();
+        write_project(temp_dir.path());
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+        vendor(&project, &temp_dir.path().join("vendor"), &OFFLINE).unwrap();
+
+        let report = vendor(&project, &temp_dir.path().join("other"), &OFFLINE).unwrap();
+
+        assert_eq!(report.packages.len(), 2);
+        let manifest = fs::read_to_string(temp_dir.path().join(MANIFEST_FILE_NAME)).unwrap();
+        assert!(manifest.contains("note = { path = \"other/value_note/0.67.0\" }"));
+        assert!(temp_dir.path().join("other/aztec/0.67.0/src/lib.nr").is_file());
+    }
+
+    #[test]
+    fn test_vendor_refuses_git_dependencies_and_nothing_to_vendor() {
+        let temp_dir = tempdir().unwrap();
+        let vendor_dir = temp_dir.path().join("vendor");
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), "[package]\nname = \"my_contract\"\n\n[dependencies]\nlocal = { path = \"../local\" }\n").unwrap();
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+
+        let error = vendor(&project, &vendor_dir, &OFFLINE).unwrap_err();
+        assert!(error.to_string().contains("No dependencies to vendor"));
+
+        fs::write(temp_dir.path().join(MANIFEST_FILE_NAME), "[package]\nname = \"my_contract\"\n\n[dependencies]\naztec = { git = \"https://github.com/AztecProtocol/aztec-packages\", tag = \"v0.67.0\" }\n").unwrap();
+        let error = vendor(&project, &vendor_dir, &OFFLINE).unwrap_err();
+        assert!(error.to_string().contains("Git dependencies cannot be vendored"));
+        assert!(error.to_string().contains("aztec = { git = \"https://github.com/AztecProtocol/aztec-packages\" }"));
+        assert!(!vendor_dir.exists());
+    }
+
+    #[test]
+    fn test_vendor_detects_drift() {
+        let temp_dir = tempdir().unwrap();
+        write_project(temp_dir.path());
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+        let vendor_dir = temp_dir.path().join("vendor");
+        vendor(&project, &vendor_dir, &OFFLINE).unwrap();
+
+        fs::write(vendor_dir.join("aztec/0.67.0/src/lib.nr"), "// patched").unwrap();
+        fs::remove_file(vendor_dir.join("value_note/0.67.0/src/lib.nr")).unwrap();
+        fs::write(vendor_dir.join("aztec/0.67.0/extra.nr"), "").unwrap();
+
+        assert_eq!(check_vendor(&vendor_dir).unwrap(), vec![
+            "modified: aztec/0.67.0/src/lib.nr".to_string(),
+            "removed: value_note/0.67.0/src/lib.nr".to_string(),
+            "added: aztec/0.67.0/extra.nr".to_string(),
+        ]);
+        assert!(vendor(&project, &vendor_dir, &OFFLINE).unwrap_err().to_string().contains("--force"));
+        assert!(vendor(&project, &vendor_dir, &VendorOptions { force: true, ..OFFLINE }).is_ok());
+        assert!(check_vendor(&vendor_dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_vendor_refuses_unrelated_folder() {
+        let temp_dir = tempdir().unwrap();
+        write_project(temp_dir.path());
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+        fs::create_dir_all(temp_dir.path().join("src")).unwrap();
+        fs::write(temp_dir.path().join("src/main.nr"), "").unwrap();
+
+        let error = vendor(&project, &temp_dir.path().join("src"), &OFFLINE).unwrap_err();
+        assert!(error.to_string().contains("was not created by vendor"));
+        // --force only discards changes of vendored files
+        assert!(vendor(&project, &temp_dir.path().join("src"), &VendorOptions { force: true, ..OFFLINE }).is_err());
+
+        assert!(temp_dir.path().join("src/main.nr").is_file());
+    }
+
+    #[test]
+    fn test_vendor_refuses_project_folders() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path().join("workspace");
+        fs::create_dir_all(root.join("member")).unwrap();
+        fs::write(root.join(MANIFEST_FILE_NAME), "[workspace]\nmembers = [\"member\"]\n").unwrap();
+        write_project(&root.join("member"));
+        // members share the dependencies folder in the workspace root
+        fs::rename(root.join("member").join(DEPENDENCIES_FOLDER_NAME), root.join(DEPENDENCIES_FOLDER_NAME)).unwrap();
+        let manifest = fs::read_to_string(root.join("member").join(MANIFEST_FILE_NAME)).unwrap().replace("\".noir-libs-deps", "\"../.noir-libs-deps");
+        fs::write(root.join("member").join(MANIFEST_FILE_NAME), manifest).unwrap();
+        let project = find_project(&root.join("member"), &PackageSelection::default()).unwrap();
+        let force = VendorOptions { force: true, ..OFFLINE };
+
+        for dir in [root.clone(), temp_dir.path().to_path_buf(), root.join("member"), root.join(DEPENDENCIES_FOLDER_NAME), root.join(DEPENDENCIES_FOLDER_NAME).join("vendor")] {
+            assert!(vendor(&project, &dir, &force).is_err(), "{}", dir.display());
+        }
+        assert!(root.join(MANIFEST_FILE_NAME).is_file());
+        assert!(root.join("member").join(MANIFEST_FILE_NAME).is_file());
+
+        assert_eq!(vendor(&project, &root.join("vendor"), &force).unwrap().packages.len(), 2);
+        // only the vendor folder is left, the temporary folders are removed
+        let entries: Vec<String> = fs::read_dir(&root).unwrap().map(|entry| entry.unwrap().file_name().to_string_lossy().to_string()).collect();
+        assert!(entries.iter().all(|entry| !entry.starts_with(".noir-libs-vendor")), "{:?}", entries);
+    }
+}
@@ -1,5 +1,7 @@
 use std::path::PathBuf;
 
+use crate::config::GIT_CHECKOUTS_FOLDER_NAME;
+
 /// Creates a full package name from package name and version
 /// Example: aztec-0.67.0
 pub fn get_full_package_name(package_name: &str, version: &str) -> String {
@@ -25,6 +27,31 @@ pub fn get_package_dir(cache_root: PathBuf, package_name: &str, version: &str) -
     cache_root.join(package_name).join(version)
 }
 
+/// Retrieves the dir where a git repository is checked out in cache
+/// Example: /home/user/.cache/noir-libs/.git-checkouts/github.com-AztecProtocol-aztec-packages/v0.67.0
+pub fn get_git_checkout_dir(cache_root: PathBuf, url: &str, tag: Option<&str>) -> PathBuf {
+    let repository = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .trim_end_matches('/')
+        .trim_end_matches(".git");
+    cache_root
+        .join(GIT_CHECKOUTS_FOLDER_NAME)
+        .join(sanitize_path_segment(repository))
+        .join(sanitize_path_segment(tag.unwrap_or("HEAD")))
+}
+
+// Replaces characters which are not safe in a single path segment, so that the result
+// can never point outside of the parent folder (e.g. "..", "/" or "\")
+fn sanitize_path_segment(segment: &str) -> String {
+    let sanitized = segment
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '_' { c } else { '-' })
+        .collect::<String>();
+    let sanitized = sanitized.trim_matches(|c| c == '-' || c == '.');
+    if sanitized.is_empty() { "-".to_string() } else { sanitized.to_string() }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,4 +106,21 @@ mod tests {
 
         assert_eq!(result.to_str().unwrap(), expected_path);
     }
+
+    #[test]
+    fn test_get_git_checkout_dir() {
+        let cache_root = PathBuf::from("deps");
+
+        let result = get_git_checkout_dir(cache_root.clone(), "https://github.com/AztecProtocol/aztec-packages.git", Some("v0.67.0"));
+        assert_eq!(result, PathBuf::from("deps").join(".git-checkouts").join("github.com-AztecProtocol-aztec-packages").join("v0.67.0"));
+
+        let result = get_git_checkout_dir(cache_root.clone(), "/tmp/repos/my_lib", None);
+        assert_eq!(result, PathBuf::from("deps").join(".git-checkouts").join("tmp-repos-my_lib").join("HEAD"));
+
+        for tag in ["..", "../../outside", "..\\outside", "/etc"] {
+            let result = get_git_checkout_dir(cache_root.clone(), "/tmp/repos/my_lib", Some(tag));
+            assert_eq!(result.parent().unwrap(), PathBuf::from("deps").join(".git-checkouts").join("tmp-repos-my_lib"), "tag {}", tag);
+            assert!(result.components().all(|component| matches!(component, std::path::Component::Normal(_))), "tag {}", tag);
+        }
+    }
 }
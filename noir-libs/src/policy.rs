@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use time::{Date, Month};
+
+use crate::config::POLICY_FILE_NAME;
+
+/// Project policy read from `noir-libs-policy.toml` in the project root. Example:
+///
+/// [audit]
+/// ignore = [
+///     { id = "NOIR-2025-0001", until = "2025-06-30", reason = "not reachable from our circuits" },
+/// ]
+///
+/// [licenses]
+/// allow = ["MIT", "Apache-2.0"]
+/// deny = ["GPL-3.0"]
+///
+/// [dependencies]
+/// allow = ["aztec", "value_note"]
+/// require-signatures = true
+/// banned-versions = { aztec = ["0.66.0"] }
+/// minimum-versions = { value_note = "0.67.0" }
+#[derive(Debug, Default, Deserialize)]
+pub struct Policy {
+    #[serde(default)]
+    pub audit: AuditPolicy,
+    #[serde(default)]
+    pub licenses: LicensePolicy,
+    #[serde(default)]
+    pub dependencies: DependencyPolicy,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditPolicy {
+    /// Advisories which are not reported
+    #[serde(default)]
+    pub ignore: Vec<IgnoredAdvisory>,
+}
+
+/// SPDX license identifiers allowed and denied for dependencies
+#[derive(Debug, Default, Deserialize)]
+pub struct LicensePolicy {
+    /// Only these licenses are allowed if not empty
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+/// Packages, versions and registries the project may depend on, checked by `add` and `fetch`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DependencyPolicy {
+    /// Only these packages are allowed if not empty
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Only packages published to these registries are allowed if not empty
+    #[serde(default)]
+    pub allowed_registries: Vec<String>,
+    #[serde(default)]
+    pub denied_registries: Vec<String>,
+    /// Banned versions by package name, exact versions or semver requirements e.g. ">=0.60.0, <0.62.0"
+    #[serde(default)]
+    pub banned_versions: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub minimum_versions: HashMap<String, String>,
+    /// Packages must be signed by a trusted key, see `noir-libs-trust.toml`
+    #[serde(default)]
+    pub require_signatures: bool,
+}
+
+impl DependencyPolicy {
+    /// Checks whether a package version may be used.
+    ///
+    /// # Returns
+    ///
+    /// The reason if the package version violates the policy.
+    pub fn check_package(&self, package_name: &str, version: &str) -> Result<(), String> {
+        if self.deny.iter().any(|denied| denied == package_name) {
+            return Err(format!("package {} is denied", package_name));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|allowed| allowed == package_name) {
+            return Err(format!("package {} is not in the allowed packages", package_name));
+        }
+        let parsed_version = semver::Version::parse(version).ok();
+        for banned in self.banned_versions.get(package_name).into_iter().flatten() {
+            let is_banned = match (semver::Version::parse(banned), &parsed_version) {
+                (Ok(banned_version), Some(version)) => &banned_version == version,
+                (Ok(_), None) => banned == version,
+                (Err(_), version) => {
+                    let requirement = semver::VersionReq::parse(banned).map_err(|_| format!("banned version {} of {} is invalid", banned, package_name))?;
+                    version.as_ref().is_some_and(|version| requirement.matches(version))
+                }
+            };
+            if is_banned {
+                return Err(format!("version {} of {} is banned ({})", version, package_name, banned));
+            }
+        }
+        if let Some(minimum) = self.minimum_versions.get(package_name) {
+            let minimum_version = semver::Version::parse(minimum).map_err(|_| format!("minimum version {} of {} is invalid", minimum, package_name))?;
+            if parsed_version.is_none_or(|version| version < minimum_version) {
+                return Err(format!("version {} of {} is lower than the minimum version {}", version, package_name, minimum));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks whether a git dependency may be used. Git dependencies are not published to a registry and
+    /// cannot be signed, so they are rejected when the policy restricts packages, registries or requires signatures.
+    ///
+    /// # Returns
+    ///
+    /// The reason if the git dependency violates the policy.
+    pub fn check_git_dependency(&self, dependency_name: &str, url: &str) -> Result<(), String> {
+        let repository_name = url.trim_end_matches('/').trim_end_matches(".git").rsplit(['/', ':']).next().unwrap_or(url);
+        if let Some(denied) = self.deny.iter().find(|denied| *denied == dependency_name || *denied == repository_name) {
+            return Err(format!("package {} is denied", denied));
+        }
+        if !self.allow.is_empty() {
+            return Err("git dependencies are not allowed when allowed packages are set".to_string());
+        }
+        if !self.allowed_registries.is_empty() {
+            return Err("git dependencies are not allowed when allowed registries are set".to_string());
+        }
+        if self.require_signatures {
+            return Err("git dependencies cannot be signed, but signatures are required".to_string());
+        }
+        Ok(())
+    }
+
+    /// Checks whether packages may be downloaded from a registry.
+    ///
+    /// # Returns
+    ///
+    /// The reason if the registry violates the policy.
+    pub fn check_registry(&self, registry: &str) -> Result<(), String> {
+        let normalize = |url: &str| url.trim_end_matches('/').to_lowercase();
+        let registry = normalize(registry);
+        if self.denied_registries.iter().any(|denied| normalize(denied) == registry) {
+            return Err(format!("registry {} is denied", registry));
+        }
+        if !self.allowed_registries.is_empty() && !self.allowed_registries.iter().any(|allowed| normalize(allowed) == registry) {
+            return Err(format!("registry {} is not in the allowed registries", registry));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IgnoredAdvisory {
+    pub id: String,
+    /// The advisory is ignored until this date (inclusive), in the format YYYY-MM-DD
+    pub until: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl IgnoredAdvisory {
+    /// Checks whether the ignore entry is still valid on the given day
+    pub fn is_active(&self, today: Date) -> Result<bool> {
+        match &self.until {
+            Some(until) => Ok(today <= parse_date(until).with_context(|| format!("Invalid expiry date of ignored advisory {}", self.id))?),
+            None => Ok(true),
+        }
+    }
+}
+
+/// Reads the policy file from the project root, the default policy is empty.
+pub fn read_policy(project_root: &Path) -> Result<Policy> {
+    let path = project_root.join(POLICY_FILE_NAME);
+    if !path.is_file() {
+        return Ok(Policy::default());
+    }
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("{} is invalid", path.display()))
+}
+
+/// Parses a date in the format YYYY-MM-DD
+/// Example: 2025-06-30
+pub fn parse_date(date: &str) -> Result<Date> {
+    let parts: Vec<&str> = date.trim().split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        anyhow::bail!("Date {} is not in the format YYYY-MM-DD", date);
+    };
+    let month: u8 = month.parse().with_context(|| format!("Invalid month in {}", date))?;
+    Ok(Date::from_calendar_date(
+        year.parse().with_context(|| format!("Invalid year in {}", date))?,
+        Month::try_from(month).with_context(|| format!("Invalid month in {}", date))?,
+        day.parse().with_context(|| format!("Invalid day in {}", date))?,
+    )?)
+}
+
+/// The current date in UTC
+pub fn today() -> Date {
+    time::OffsetDateTime::now_utc().date()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2025-06-30").unwrap(), Date::from_calendar_date(2025, Month::June, 30).unwrap());
+        assert!(parse_date("2025-13-01").is_err());
+        assert!(parse_date("30.06.2025").is_err());
+    }
+
+    #[test]
+    fn test_dependency_policy() {
+        let policy: Policy = toml::from_str(indoc::indoc! {r#"
+            [dependencies]
+            deny = ["evil"]
+            allowed-registries = ["https://api.noir-libs.org/api/v1/"]
+            banned-versions = { aztec = ["0.66.0", ">=0.60.0, <0.62.0"] }
+            minimum-versions = { value_note = "0.67.0" }
+        "#}).unwrap();
+        let policy = policy.dependencies;
+
+        assert!(policy.check_package("evil", "1.0.0").unwrap_err().contains("is denied"));
+        assert!(policy.check_package("aztec", "0.66.0").unwrap_err().contains("is banned"));
+        assert!(policy.check_package("aztec", "0.61.3").unwrap_err().contains("is banned"));
+        assert!(policy.check_package("aztec", "0.67.0").is_ok());
+        assert!(policy.check_package("value_note", "0.66.1").unwrap_err().contains("lower than the minimum version"));
+        assert!(policy.check_package("value_note", "0.67.0").is_ok());
+        assert!(policy.check_registry("https://api.noir-libs.org/api/v1").is_ok());
+        assert!(policy.check_registry("https://example.com/api/v1").is_err());
+    }
+
+    #[test]
+    fn test_dependency_allow_list() {
+        let policy = DependencyPolicy { allow: vec!["aztec".to_string()], ..DependencyPolicy::default() };
+
+        assert!(policy.check_package("aztec", "0.67.0").is_ok());
+        assert!(policy.check_package("random_lib", "1.0.0").unwrap_err().contains("not in the allowed packages"));
+    }
+
+    #[test]
+    fn test_git_dependency_policy() {
+        let url = "https://github.com/AztecProtocol/aztec-packages.git";
+        let deny = DependencyPolicy { deny: vec!["aztec-packages".to_string()], ..DependencyPolicy::default() };
+        let allow = DependencyPolicy { allow: vec!["aztec".to_string()], ..DependencyPolicy::default() };
+        let signatures = DependencyPolicy { require_signatures: true, ..DependencyPolicy::default() };
+
+        assert!(DependencyPolicy::default().check_git_dependency("aztec", url).is_ok());
+        assert!(deny.check_git_dependency("aztec", url).unwrap_err().contains("aztec-packages is denied"));
+        assert!(allow.check_git_dependency("aztec", url).unwrap_err().contains("not allowed"));
+        assert!(signatures.check_git_dependency("aztec", url).unwrap_err().contains("signatures are required"));
+    }
+
+    #[test]
+    fn test_read_policy() {
+        let temp_dir = tempdir().unwrap();
+        assert!(read_policy(temp_dir.path()).unwrap().audit.ignore.is_empty());
+
+        fs::write(temp_dir.path().join(POLICY_FILE_NAME), indoc::indoc! {r#"
+            [audit]
+            ignore = [
+                { id = "NOIR-2025-0001", until = "2025-06-30", reason = "not reachable" },
+                { id = "NOIR-2025-0002" },
+            ]
+
+            [licenses]
+            deny = ["GPL-3.0"]
+        "#}).unwrap();
+        let policy = read_policy(temp_dir.path()).unwrap();
+
+        let [expiring, permanent] = policy.audit.ignore.as_slice() else { panic!("Expected two ignored advisories") };
+        assert!(expiring.is_active(parse_date("2025-06-30").unwrap()).unwrap());
+        assert!(!expiring.is_active(parse_date("2025-07-01").unwrap()).unwrap());
+        assert!(permanent.is_active(parse_date("2099-01-01").unwrap()).unwrap());
+        assert!(policy.licenses.allow.is_empty());
+        assert_eq!(policy.licenses.deny, vec!["GPL-3.0".to_string()]);
+    }
+}
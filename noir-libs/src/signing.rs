@@ -0,0 +1,230 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::{SIGNATURE_FILE_NAME, SIGNING_KEY_ENV_VAR_NAME};
+
+const SIGNATURE_ALGORITHM: &str = "ed25519";
+
+/// Signature of package contents stored in the package root
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageSignature {
+    pub algorithm: String,
+    /// Hex encoded public key of the signer
+    pub public_key: String,
+    /// Hex encoded SHA-256 digest of the package contents, see [`package_digest`]
+    pub digest: String,
+    /// Hex encoded signature of the digest
+    pub signature: String,
+}
+
+/// The signing key used when no key path is given: `NOIR_LIBS_SIGNING_KEY` or the user config folder
+/// Example: /home/user/.config/noir-libs/signing-key
+pub fn default_signing_key_path() -> Result<PathBuf> {
+    if let Ok(path) = std::env::var(SIGNING_KEY_ENV_VAR_NAME) {
+        return Ok(PathBuf::from(path));
+    }
+    let dirs = directories::ProjectDirs::from("org", "noir-libs", "noir-libs").context("Unable to find the user config folder")?;
+    Ok(dirs.config_dir().join("signing-key"))
+}
+
+/// Generates a new signing key and saves it hex encoded, readable only by the user.
+///
+/// # Returns
+///
+/// The generated key.
+pub fn generate_signing_key(path: &Path, overwrite: bool) -> Result<SigningKey> {
+    if path.exists() && !overwrite {
+        bail!("Signing key {} already exists. Use --force to replace it.", path.display());
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(!overwrite);
+    if overwrite {
+        options.create(true).truncate(true);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        // the key is never written to a file readable by other users
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).with_context(|| format!("Cannot create signing key {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        // the mode applies only to new files, a replaced key file keeps its permissions otherwise
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+    }
+    let key = SigningKey::generate(&mut OsRng);
+    file.write_all((hex::encode(key.to_bytes()) + "\n").as_bytes()).with_context(|| format!("Cannot write signing key {}", path.display()))?;
+    Ok(key)
+}
+
+pub fn read_signing_key(path: &Path) -> Result<SigningKey> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read signing key {}. Generate one with \"noir-libs key generate\".", path.display()))?;
+    let bytes: [u8; 32] = hex::decode(content.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .with_context(|| format!("Signing key {} is invalid", path.display()))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+pub fn encode_public_key(key: &VerifyingKey) -> String {
+    hex::encode(key.to_bytes())
+}
+
+/// Computes the digest of package contents: SHA-256 over the sorted relative paths
+/// and SHA-256 hashes of all files except the signature file.
+pub fn package_digest(package_dir: &Path) -> Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    for entry in walkdir::WalkDir::new(package_dir).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative_path = entry.path().strip_prefix(package_dir)?;
+        if relative_path == Path::new(SIGNATURE_FILE_NAME) {
+            continue;
+        }
+        let path: Vec<String> = relative_path.components().map(|component| component.as_os_str().to_string_lossy().to_string()).collect();
+        hasher.update(path.join("/").as_bytes());
+        hasher.update([0]);
+        hasher.update(Sha256::digest(std::fs::read(entry.path())?));
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// Signs the package contents and writes the signature file into the package root.
+pub fn sign_package(package_dir: &Path, key: &SigningKey) -> Result<PackageSignature> {
+    let digest = package_digest(package_dir)?;
+    let signature = PackageSignature {
+        algorithm: SIGNATURE_ALGORITHM.to_string(),
+        public_key: encode_public_key(&key.verifying_key()),
+        digest: hex::encode(digest),
+        signature: hex::encode(key.sign(&digest).to_bytes()),
+    };
+    std::fs::write(package_dir.join(SIGNATURE_FILE_NAME), serde_json::to_string_pretty(&signature)? + "\n")?;
+    Ok(signature)
+}
+
+/// Reads the signature file of a package.
+///
+/// # Returns
+///
+/// `None` if the package is not signed.
+pub fn read_signature(package_dir: &Path) -> Result<Option<PackageSignature>> {
+    let path = package_dir.join(SIGNATURE_FILE_NAME);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)?;
+    Ok(Some(serde_json::from_str(&content).with_context(|| format!("{} is invalid", path.display()))?))
+}
+
+/// Verifies that the signature matches the package contents and was made by its public key.
+pub fn verify_signature(package_dir: &Path, signature: &PackageSignature) -> Result<()> {
+    if signature.algorithm != SIGNATURE_ALGORITHM {
+        bail!("Unsupported signature algorithm {}", signature.algorithm);
+    }
+    let public_key: [u8; 32] = hex::decode(&signature.public_key).ok().and_then(|bytes| bytes.try_into().ok()).context("Signature public key is invalid")?;
+    let public_key = VerifyingKey::from_bytes(&public_key).context("Signature public key is invalid")?;
+    let signature_bytes: [u8; 64] = hex::decode(&signature.signature).ok().and_then(|bytes| bytes.try_into().ok()).context("Signature is invalid")?;
+
+    let digest = package_digest(package_dir)?;
+    if hex::encode(digest) != signature.digest {
+        bail!("Package contents do not match the signed digest, the package was modified after signing");
+    }
+    public_key
+        .verify(&digest, &Signature::from_bytes(&signature_bytes))
+        .context("Signature does not match the package contents")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write_package(dir: &Path) {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/lib.nr"), "pub fn f() {}").unwrap();
+        fs::write(dir.join("Nargo.toml"), "[package]\nname = \"my_lib\"\n").unwrap();
+    }
+
+    #[test]
+    fn test_generate_and_read_signing_key() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("keys/signing-key");
+
+        let key = generate_signing_key(&path, false).unwrap();
+
+        assert_eq!(read_signing_key(&path).unwrap().to_bytes(), key.to_bytes());
+        assert!(generate_signing_key(&path, false).is_err());
+        assert!(generate_signing_key(&path, true).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signing_key_is_readable_only_by_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("signing-key");
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        generate_signing_key(&path, false).unwrap();
+        assert_eq!(mode(&path), 0o600);
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        let key = generate_signing_key(&path, true).unwrap();
+        assert_eq!(mode(&path), 0o600);
+        assert_eq!(read_signing_key(&path).unwrap().to_bytes(), key.to_bytes());
+    }
+
+    #[test]
+    fn test_sign_and_verify_package() {
+        let temp_dir = tempdir().unwrap();
+        let package_dir = temp_dir.path().join("package");
+        write_package(&package_dir);
+        let key = SigningKey::generate(&mut OsRng);
+
+        let signature = sign_package(&package_dir, &key).unwrap();
+
+        assert_eq!(read_signature(&package_dir).unwrap(), Some(signature.clone()));
+        assert_eq!(signature.public_key, encode_public_key(&key.verifying_key()));
+        assert!(verify_signature(&package_dir, &signature).is_ok());
+
+        fs::write(package_dir.join("src/lib.nr"), "pub fn tampered() {}").unwrap();
+        assert!(verify_signature(&package_dir, &signature).unwrap_err().to_string().contains("modified after signing"));
+    }
+
+    #[test]
+    fn test_verify_signature_of_other_key() {
+        let temp_dir = tempdir().unwrap();
+        write_package(temp_dir.path());
+        let mut signature = sign_package(temp_dir.path(), &SigningKey::generate(&mut OsRng)).unwrap();
+
+        signature.public_key = encode_public_key(&SigningKey::generate(&mut OsRng).verifying_key());
+
+        assert!(verify_signature(temp_dir.path(), &signature).is_err());
+    }
+
+    #[test]
+    fn test_package_digest_ignores_signature_file() {
+        let temp_dir = tempdir().unwrap();
+        write_package(temp_dir.path());
+        let digest = package_digest(temp_dir.path()).unwrap();
+
+        fs::write(temp_dir.path().join(SIGNATURE_FILE_NAME), "{}").unwrap();
+
+        assert_eq!(package_digest(temp_dir.path()).unwrap(), digest);
+    }
+}
@@ -1,33 +1,57 @@
 use std::fs;
-use flate2::write::GzEncoder;
-use flate2::Compression;
+use flate2::{Compression, GzBuilder};
 use std::fs::File;
 use std::io::{self, BufWriter};
 use std::path::Path;
 use flate2::read::GzDecoder;
-use tar::{Archive, Builder};
+use tar::{Archive, Builder, EntryType, Header};
+
+/// Environment variable with the timestamp used as modification time of archived files
+/// See https://reproducible-builds.org/specs/source-date-epoch/
+pub const SOURCE_DATE_EPOCH_ENV_VAR_NAME: &str = "SOURCE_DATE_EPOCH";
 
 /// Compress and package a directory into a `.tar.gz` archive.
 ///
 /// The resulting `.tar.gz` will contain the directory itself (not just its contents).
+/// The archive is reproducible: entries are sorted by path, owner is normalized to root,
+/// modes to 0644 (0755 for folders and executables) and modification times to
+/// `SOURCE_DATE_EPOCH` (or 0 if not set), and the gzip header has no timestamp.
 pub fn create_tar_gz(src_folder: &Path, dst_path: &Path) -> io::Result<()> {
+    let mtime = std::env::var(SOURCE_DATE_EPOCH_ENV_VAR_NAME)
+        .ok()
+        .and_then(|epoch| epoch.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    create_tar_gz_with_mtime(src_folder, dst_path, mtime)
+}
+
+fn create_tar_gz_with_mtime(src_folder: &Path, dst_path: &Path, mtime: u64) -> io::Result<()> {
     let tar_gz = File::create(dst_path)?;
     let enc_writer = BufWriter::new(tar_gz);
-    let encoder = GzEncoder::new(enc_writer, Compression::default());
+    let encoder = GzBuilder::new().mtime(0).write(enc_writer, Compression::default());
 
     let mut tar = Builder::new(encoder);
 
-    // package all files inside src_folder
-    for entry in fs::read_dir(src_folder)? {
+    // package all files inside src_folder in a stable order
+    for entry in walkdir::WalkDir::new(src_folder).min_depth(1).sort_by_file_name() {
         let entry = entry?;
         let path = entry.path();
         let relative_path = path.strip_prefix(src_folder).unwrap();
+        let metadata = fs::metadata(path)?;
 
-        if path.is_dir() {
-            tar.append_dir_all(relative_path, &path)?;
+        let mut header = Header::new_gnu();
+        header.set_mtime(mtime);
+        header.set_uid(0);
+        header.set_gid(0);
+        if metadata.is_dir() {
+            header.set_entry_type(EntryType::Directory);
+            header.set_mode(0o755);
+            header.set_size(0);
+            tar.append_data(&mut header, relative_path, io::empty())?;
         } else {
-            let mut file = File::open(&path)?;
-            tar.append_file(relative_path, &mut file)?;
+            header.set_entry_type(EntryType::Regular);
+            header.set_mode(if is_executable(&metadata) { 0o755 } else { 0o644 });
+            header.set_size(metadata.len());
+            tar.append_data(&mut header, relative_path, File::open(path)?)?;
         }
     }
 
@@ -36,6 +60,17 @@ pub fn create_tar_gz(src_folder: &Path, dst_path: &Path) -> io::Result<()> {
     Ok(())
 }
 
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
 /// Extracts a `.tar.gz` archive to the specified destination folder.
 ///
 /// The archive must contain a directory, which will be extracted into `dst_folder`.
@@ -54,7 +89,10 @@ mod tests {
     use std::path::Path;
     use tempfile::tempdir;
     use crate::config::MANIFEST_FILE_NAME;
-    use crate::tar::extract_tar_gz;
+    use crate::tar::{create_tar_gz, create_tar_gz_with_mtime, extract_tar_gz};
+    use std::fs;
+    use flate2::read::GzDecoder;
+    use tar::Archive;
 
     const TEST_PACKAGE: &str = "tests/test_files/test_package-1.2.3";
 
@@ -67,4 +105,52 @@ mod tests {
         assert!(result.is_ok());
         assert!(temp_dir.path().join(MANIFEST_FILE_NAME).exists()); // Extracted files should include manifest
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_create_tar_gz_is_reproducible() {
+        let temp_dir = tempdir().unwrap();
+        let first_src = temp_dir.path().join("first");
+        let second_src = temp_dir.path().join("second");
+        // same content created in a different order at different times
+        for (src, files) in [(&first_src, ["src/lib.nr", "Nargo.toml", "README.md"]), (&second_src, ["README.md", "Nargo.toml", "src/lib.nr"])] {
+            for file in files {
+                let path = src.join(file);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, file).unwrap();
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let first_tarball = temp_dir.path().join("first.tar.gz");
+        let second_tarball = temp_dir.path().join("second.tar.gz");
+
+        create_tar_gz(&first_src, &first_tarball).unwrap();
+        create_tar_gz(&second_src, &second_tarball).unwrap();
+
+        assert_eq!(fs::read(&first_tarball).unwrap(), fs::read(&second_tarball).unwrap());
+    }
+
+    #[test]
+    fn test_create_tar_gz_normalizes_headers() {
+        let temp_dir = tempdir().unwrap();
+        let src = temp_dir.path().join("src_package");
+        fs::create_dir_all(src.join("src")).unwrap();
+        fs::write(src.join("src/lib.nr"), "").unwrap();
+        fs::write(src.join(MANIFEST_FILE_NAME), "").unwrap();
+        let tarball = temp_dir.path().join("package.tar.gz");
+
+        create_tar_gz_with_mtime(&src, &tarball, 1700000000).unwrap();
+
+        let mut archive = Archive::new(GzDecoder::new(fs::File::open(&tarball).unwrap()));
+        let entries: Vec<(String, u32, u64, u64)> = archive.entries().unwrap()
+            .map(|entry| {
+                let header = entry.unwrap().header().clone();
+                (header.path().unwrap().to_str().unwrap().to_string(), header.mode().unwrap(), header.mtime().unwrap(), header.uid().unwrap())
+            })
+            .collect();
+        assert_eq!(entries, vec![
+            (MANIFEST_FILE_NAME.to_string(), 0o644, 1700000000, 0),
+            ("src".to_string(), 0o755, 1700000000, 0),
+            ("src/lib.nr".to_string(), 0o644, 1700000000, 0),
+        ]);
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use serde::Deserialize;
+
+use crate::api::api::get_package_keys_api;
+use crate::config::{KEYS_FOLDER_NAME, TRUST_FILE_NAME};
+use crate::policy::read_policy;
+use crate::signing::{read_signature, verify_signature};
+
+// Keys pinned for this name are trusted for all packages
+const ANY_PACKAGE: &str = "*";
+
+/// How packages without a trusted signature are handled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnsignedPolicy {
+    #[default]
+    Allow,
+    Warn,
+    Reject,
+}
+
+/// Trust configuration of a project. Example:
+///
+/// unsigned = "reject"
+///
+/// [keys]
+/// aztec = ["<hex encoded public key>"]
+/// "*" = ["<key trusted for all packages>"]
+#[derive(Debug, Default, Deserialize)]
+pub struct TrustConfig {
+    #[serde(default)]
+    pub unsigned: UnsignedPolicy,
+    /// Pinned public keys by package name
+    #[serde(default)]
+    pub keys: HashMap<String, Vec<String>>,
+}
+
+impl TrustConfig {
+    fn pinned_keys(&self, package_name: &str) -> Vec<String> {
+        [package_name, ANY_PACKAGE]
+            .iter()
+            .filter_map(|name| self.keys.get(*name))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+}
+
+/// Reads the trust file from the project root, the default configuration allows unsigned packages.
+pub fn read_trust_config(project_root: &Path) -> Result<TrustConfig> {
+    let path = project_root.join(TRUST_FILE_NAME);
+    if !path.is_file() {
+        return Ok(TrustConfig::default());
+    }
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Cannot read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("{} is invalid", path.display()))
+}
+
+/// Verifies the signature of an extracted package against the trust configuration of the project.
+///
+/// A signature must always match the package contents, also when unsigned packages are allowed.
+/// The signer is trusted if its key is pinned in the trust file or published by the package owners
+/// in the registry. Packages pinned in the trust file must be signed by a trusted key, other packages
+/// without a trusted signature are handled according to the `unsigned` policy, they are rejected
+/// if the project policy requires signatures. Owner keys are cached, so verification works offline.
+///
+/// # Arguments
+///
+/// * `cache_root` - The dependencies folder, its parent is the project root with the trust file.
+/// * `package_dir` - The extracted package.
+/// * `offline` - Use only cached owner keys.
+pub fn verify_package_trust(cache_root: &Path, package_dir: &Path, package_name: &str, version: &str, offline: bool) -> Result<()> {
+    let project_root = cache_root.parent().unwrap_or(cache_root);
+    let mut config = read_trust_config(project_root)?;
+    if read_policy(project_root)?.dependencies.require_signatures {
+        config.unsigned = UnsignedPolicy::Reject;
+    }
+    let pinned = config.pinned_keys(package_name);
+
+    // a present signature must always match the contents, the policy only decides which signers are trusted
+    let signature = read_signature(package_dir)?;
+    if let Some(signature) = &signature {
+        verify_signature(package_dir, signature).with_context(|| format!("Signature of package {}@{} is invalid", package_name, version))?;
+    }
+    if config.unsigned == UnsignedPolicy::Allow && pinned.is_empty() {
+        return Ok(());
+    }
+
+    let Some(signature) = signature else {
+        if !pinned.is_empty() {
+            bail!("Package {}@{} is not signed, but {} requires a signature by a pinned key", package_name, version, TRUST_FILE_NAME);
+        }
+        return apply_unsigned_policy(config.unsigned, &format!("Package {}@{} is not signed", package_name, version));
+    };
+
+    let mut trusted = pinned;
+    trusted.extend(get_owner_keys(cache_root, package_name, offline)?);
+    if trusted.contains(&signature.public_key) {
+        Ok(())
+    } else if trusted.is_empty() {
+        apply_unsigned_policy(config.unsigned, &format!("Package {}@{} is signed by unknown key {}", package_name, version, signature.public_key))
+    } else {
+        bail!("Package {}@{} is signed by key {} which is not trusted", package_name, version, signature.public_key)
+    }
+}
+
+fn apply_unsigned_policy(policy: UnsignedPolicy, message: &str) -> Result<()> {
+    match policy {
+        UnsignedPolicy::Allow => Ok(()),
+        UnsignedPolicy::Warn => {
+            println!("{}", format!("Warning: {}", message).yellow());
+            Ok(())
+        }
+        UnsignedPolicy::Reject => bail!("{}. Unsigned packages are rejected by {}.", message, TRUST_FILE_NAME),
+    }
+}
+
+/// Retrieves signing keys published by the owners of a package. Keys are downloaded when online
+/// and cached, the cached keys are used offline or when the registry is unavailable.
+/// There is no command to publish keys yet, so the list is empty unless the registry provides keys
+/// by other means and signatures are trusted through pinned keys only.
+fn get_owner_keys(cache_root: &Path, package_name: &str, offline: bool) -> Result<Vec<String>> {
+    let cache_path = get_owner_keys_path(cache_root, package_name);
+    if !offline {
+        match get_package_keys_api(package_name) {
+            Ok(keys) => {
+                std::fs::create_dir_all(cache_root.join(KEYS_FOLDER_NAME))?;
+                std::fs::write(&cache_path, serde_json::to_string(&keys)?)?;
+                return Ok(keys);
+            }
+            Err(e) => println!("{}", format!("Warning: unable to download signing keys of {}: {}", package_name, e).yellow()),
+        }
+    }
+    if !cache_path.is_file() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&cache_path)?;
+    serde_json::from_str(&content).with_context(|| format!("{} is invalid", cache_path.display()))
+}
+
+/// Example: .noir-libs-deps/.keys/aztec.json
+fn get_owner_keys_path(cache_root: &Path, package_name: &str) -> PathBuf {
+    cache_root.join(KEYS_FOLDER_NAME).join(format!("{}.json", package_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEPENDENCIES_FOLDER_NAME;
+    use crate::signing::{encode_public_key, sign_package};
+    use ed25519_dalek::SigningKey;
+    use rand_core::OsRng;
+    use std::fs;
+    use tempfile::{tempdir, TempDir};
+
+    // project with an extracted package, returns (project, cache root, package dir)
+    fn setup(trust: Option<&str>) -> (TempDir, PathBuf, PathBuf) {
+        let temp_dir = tempdir().unwrap();
+        let cache_root = temp_dir.path().join(DEPENDENCIES_FOLDER_NAME);
+        let package_dir = cache_root.join("my_lib/1.0.0");
+        fs::create_dir_all(&package_dir).unwrap();
+        fs::write(package_dir.join("Nargo.toml"), "[package]\nname = \"my_lib\"\n").unwrap();
+        if let Some(trust) = trust {
+            fs::write(temp_dir.path().join(TRUST_FILE_NAME), trust).unwrap();
+        }
+        (temp_dir, cache_root, package_dir)
+    }
+
+    #[test]
+    fn test_unsigned_policy() {
+        let (_temp_dir, cache_root, package_dir) = setup(None);
+        assert!(verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).is_ok());
+
+        let (_temp_dir, cache_root, package_dir) = setup(Some("unsigned = \"warn\"\n"));
+        assert!(verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).is_ok());
+
+        let (_temp_dir, cache_root, package_dir) = setup(Some("unsigned = \"reject\"\n"));
+        let error = verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).unwrap_err();
+        assert!(error.to_string().contains("is not signed"));
+
+        let (temp_dir, cache_root, package_dir) = setup(None);
+        fs::write(temp_dir.path().join(crate::config::POLICY_FILE_NAME), "[dependencies]\nrequire-signatures = true\n").unwrap();
+        assert!(verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).is_err());
+    }
+
+    #[test]
+    fn test_pinned_key() {
+        let key = SigningKey::generate(&mut OsRng);
+        let trust = format!("[keys]\nmy_lib = [\"{}\"]\n", encode_public_key(&key.verifying_key()));
+
+        let (_temp_dir, cache_root, package_dir) = setup(Some(&trust));
+        assert!(verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).is_err());
+
+        sign_package(&package_dir, &key).unwrap();
+        assert!(verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).is_ok());
+
+        sign_package(&package_dir, &SigningKey::generate(&mut OsRng)).unwrap();
+        let error = verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).unwrap_err();
+        assert!(error.to_string().contains("is not trusted"));
+    }
+
+    #[test]
+    fn test_cached_owner_keys_offline() {
+        let key = SigningKey::generate(&mut OsRng);
+        let (_temp_dir, cache_root, package_dir) = setup(Some("unsigned = \"reject\"\n"));
+        sign_package(&package_dir, &key).unwrap();
+
+        // signed by unknown key
+        assert!(verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).is_err());
+
+        fs::create_dir_all(cache_root.join(KEYS_FOLDER_NAME)).unwrap();
+        fs::write(get_owner_keys_path(&cache_root, "my_lib"), format!("[\"{}\"]", encode_public_key(&key.verifying_key()))).unwrap();
+        assert!(verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).is_ok());
+
+        // tampered package
+        fs::write(package_dir.join("Nargo.toml"), "[package]\nname = \"tampered\"\n").unwrap();
+        let error = verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).unwrap_err();
+        assert!(error.to_string().contains("Signature of package my_lib@1.0.0 is invalid"));
+    }
+
+    #[test]
+    fn test_tampered_package_with_default_config() {
+        let (_temp_dir, cache_root, package_dir) = setup(None);
+        sign_package(&package_dir, &SigningKey::generate(&mut OsRng)).unwrap();
+        assert!(verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).is_ok());
+
+        fs::write(package_dir.join("Nargo.toml"), "[package]\nname = \"tampered\"\n").unwrap();
+        let error = verify_package_trust(&cache_root, &package_dir, "my_lib", "1.0.0", true).unwrap_err();
+        assert!(error.to_string().contains("Signature of package my_lib@1.0.0 is invalid"));
+    }
+}
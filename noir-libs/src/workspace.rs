@@ -0,0 +1,242 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::config::{DEPENDENCIES_FOLDER_NAME, MANIFEST_FILE_NAME};
+use crate::manifest::try_find_manifest;
+
+#[derive(Debug, Deserialize)]
+struct WorkspaceManifest {
+    workspace: WorkspaceConfig,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkspaceConfig {
+    members: Vec<String>,
+    #[serde(rename = "default-member")]
+    default_member: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MemberManifest {
+    package: Option<MemberPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MemberPackage {
+    name: Option<String>,
+}
+
+/// A Nargo workspace, a root manifest with `[workspace]` table listing member packages
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub members: Vec<PathBuf>,
+    pub default_member: Option<PathBuf>,
+}
+
+/// Packages selected by command line options: `--workspace` or `-p <member>`
+#[derive(Debug, Clone, Default)]
+pub struct PackageSelection {
+    pub workspace: bool,
+    pub package: Option<String>,
+}
+
+/// The packages a command operates on and the dependencies folder shared by them
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// Folder containing the dependencies folder, the workspace root for workspaces
+    pub root: PathBuf,
+    /// Folders of the selected packages
+    pub packages: Vec<PathBuf>,
+    /// Manifests of all packages sharing the dependencies folder
+    pub all_manifests: Vec<PathBuf>,
+}
+
+impl Project {
+    /// The dependencies folder shared by all packages of the project
+    /// Example: /home/user/my_workspace/.noir-libs-deps
+    pub fn cache_root(&self) -> PathBuf {
+        self.root.join(DEPENDENCIES_FOLDER_NAME)
+    }
+}
+
+impl Workspace {
+    /// Finds a member by its package name or by its path relative to the workspace root.
+    pub fn find_member(&self, name: &str) -> Result<PathBuf> {
+        self.members
+            .iter()
+            .find(|member| {
+                read_package_name(member).as_deref() == Some(name) || member.strip_prefix(&self.root).is_ok_and(|path| path == Path::new(name))
+            })
+            .cloned()
+            .with_context(|| format!("Package {} is not a member of the workspace {}", name, self.root.display()))
+    }
+
+    /// Finds the member containing the given directory.
+    pub fn member_containing(&self, dir: &Path) -> Option<PathBuf> {
+        self.members.iter().find(|member| dir.starts_with(member)).cloned()
+    }
+
+    pub fn manifests(&self) -> Vec<PathBuf> {
+        self.members.iter().map(|member| member.join(MANIFEST_FILE_NAME)).collect()
+    }
+}
+
+/// Reads a workspace manifest from the given directory.
+///
+/// # Returns
+///
+/// `None` if the directory has no manifest or the manifest has no `[workspace]` table.
+pub fn read_workspace(dir: &Path) -> Result<Option<Workspace>> {
+    let manifest_path = dir.join(MANIFEST_FILE_NAME);
+    if !manifest_path.is_file() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&manifest_path).with_context(|| format!("Cannot read {} manifest file", manifest_path.display()))?;
+    let doc: toml::Value = toml::from_str(&content).with_context(|| format!("{} manifest file is invalid TOML.", manifest_path.display()))?;
+    if doc.get("workspace").is_none() {
+        return Ok(None);
+    }
+    let manifest: WorkspaceManifest = doc.try_into().with_context(|| format!("Failed to parse [workspace] in {}. Assure members are listed. Example:
+
+[workspace]
+members = [\"crates/a\", \"crates/b\"]", manifest_path.display()))?;
+
+    Ok(Some(Workspace {
+        root: dir.to_path_buf(),
+        members: manifest.workspace.members.iter().map(|member| dir.join(member)).collect(),
+        default_member: manifest.workspace.default_member.map(|member| dir.join(member)),
+    }))
+}
+
+/// Finds the workspace the given directory belongs to: the nearest workspace whose root
+/// is the directory itself or whose member contains the directory.
+pub fn find_workspace(start_dir: &Path) -> Result<Option<Workspace>> {
+    for dir in start_dir.ancestors() {
+        if let Some(workspace) = read_workspace(dir)? {
+            if dir == start_dir || workspace.member_containing(start_dir).is_some() {
+                return Ok(Some(workspace));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Determines the packages a command operates on.
+///
+/// Inside a workspace member the member is selected. At the workspace root the default member
+/// is selected, or all members if there is no default member. `--workspace` selects all members
+/// and `-p <name>` selects a single member.
+pub fn find_project(current_dir: &Path, selection: &PackageSelection) -> Result<Project> {
+    if let Some(workspace) = find_workspace(current_dir)? {
+        let packages = if let Some(name) = &selection.package {
+            vec![workspace.find_member(name)?]
+        } else if selection.workspace {
+            workspace.members.clone()
+        } else if let Some(member) = workspace.member_containing(current_dir) {
+            vec![member]
+        } else if let Some(default_member) = &workspace.default_member {
+            vec![default_member.clone()]
+        } else {
+            workspace.members.clone()
+        };
+        return Ok(Project {
+            all_manifests: workspace.manifests(),
+            root: workspace.root,
+            packages,
+        });
+    }
+
+    let manifest_path = try_find_manifest(current_dir)
+        .with_context(|| format!("Unable to find {} manifest file. Please verify you are in the correct directory.", &MANIFEST_FILE_NAME))?;
+    let package_dir = manifest_path.parent().expect("Failed to get manifest parent directory").to_path_buf();
+    if let Some(name) = &selection.package {
+        if read_package_name(&package_dir).as_deref() != Some(name.as_str()) {
+            bail!("Package {} not found. The -p option selects a member of a workspace, but {} is not a workspace.", name, manifest_path.display());
+        }
+    }
+    Ok(Project {
+        root: package_dir.clone(),
+        packages: vec![package_dir],
+        all_manifests: vec![manifest_path],
+    })
+}
+
+fn read_package_name(package_dir: &Path) -> Option<String> {
+    let content = std::fs::read_to_string(package_dir.join(MANIFEST_FILE_NAME)).ok()?;
+    let manifest: MemberManifest = toml::from_str(&content).ok()?;
+    manifest.package?.name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn setup_workspace(root: &Path, default_member: Option<&str>) {
+        let default_member = default_member.map(|m| format!("default-member = \"{}\"\n", m)).unwrap_or_default();
+        fs::write(root.join(MANIFEST_FILE_NAME), format!("[workspace]\nmembers = [\"crates/a\", \"crates/b\"]\n{}", default_member)).unwrap();
+        for member in ["a", "b"] {
+            let dir = root.join("crates").join(member);
+            fs::create_dir_all(dir.join("src")).unwrap();
+            fs::write(dir.join(MANIFEST_FILE_NAME), format!("[package]\nname = \"pkg_{}\"\ntype = \"lib\"\n", member)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_find_project_in_workspace() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        setup_workspace(root, None);
+        let member_a = root.join("crates/a");
+        let member_b = root.join("crates/b");
+
+        let project = find_project(root, &PackageSelection::default()).unwrap();
+        assert_eq!(project.root, root);
+        assert_eq!(project.packages, vec![member_a.clone(), member_b.clone()]);
+        assert_eq!(project.all_manifests.len(), 2);
+        assert_eq!(project.cache_root(), root.join(DEPENDENCIES_FOLDER_NAME));
+
+        let project = find_project(&member_a.join("src"), &PackageSelection::default()).unwrap();
+        assert_eq!(project.root, root);
+        assert_eq!(project.packages, vec![member_a.clone()]);
+
+        let project = find_project(&member_a, &PackageSelection { workspace: true, package: None }).unwrap();
+        assert_eq!(project.packages, vec![member_a.clone(), member_b.clone()]);
+
+        let project = find_project(root, &PackageSelection { workspace: false, package: Some("pkg_b".to_string()) }).unwrap();
+        assert_eq!(project.packages, vec![member_b.clone()]);
+
+        let project = find_project(root, &PackageSelection { workspace: false, package: Some("crates/a".to_string()) }).unwrap();
+        assert_eq!(project.packages, vec![member_a]);
+
+        assert!(find_project(root, &PackageSelection { workspace: false, package: Some("unknown".to_string()) }).is_err());
+    }
+
+    #[test]
+    fn test_find_project_default_member() {
+        let temp_dir = tempdir().unwrap();
+        setup_workspace(temp_dir.path(), Some("crates/b"));
+
+        let project = find_project(temp_dir.path(), &PackageSelection::default()).unwrap();
+        assert_eq!(project.packages, vec![temp_dir.path().join("crates/b")]);
+    }
+
+    #[test]
+    fn test_find_project_single_package() {
+        let temp_dir = tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join(MANIFEST_FILE_NAME), "[package]\nname = \"single\"\n").unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+
+        let project = find_project(&root.join("src"), &PackageSelection { workspace: true, package: None }).unwrap();
+        assert_eq!(project.root, root);
+        assert_eq!(project.packages, vec![root.to_path_buf()]);
+
+        assert!(find_project(root, &PackageSelection { workspace: false, package: Some("single".to_string()) }).is_ok());
+        assert!(find_project(root, &PackageSelection { workspace: false, package: Some("other".to_string()) }).is_err());
+    }
+}
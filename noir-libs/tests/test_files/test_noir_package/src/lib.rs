@@ -1 +0,0 @@
-myfile
\ No newline at end of file